@@ -37,5 +37,98 @@ pub enum ErrorCode {
     Overflow,
     #[msg("Insufficient tokens")]
     InsufficientTokens,
-       
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Collateral mint has an unsupported Token-2022 extension")]
+    UnsupportedCollateralMint,
+    #[msg("Collateral received by the vault does not match the expected amount")]
+    CollateralMismatch,
+    #[msg("Price exponent out of supported range")]
+    InvalidPriceExponent,
+    #[msg("Price cannot be rescaled without losing precision")]
+    PricePrecisionLoss,
+    #[msg("Invalid asset config")]
+    InvalidAssetConfig,
+    #[msg("Oracle confidence interval too wide")]
+    PriceConfidenceTooWide,
+    #[msg("Price is within the oracle confidence band of the strike")]
+    PriceTooCloseToStrike,
+    #[msg("Price update was not published within the settlement window after expiry")]
+    PriceOutsideSettlementWindow,
+    #[msg("Force resolution is only available after the oracle timeout")]
+    ForceResolveTooEarly,
+    #[msg("Market is not open")]
+    MarketNotOpen,
+    #[msg("Market is halted")]
+    MarketHalted,
+    #[msg("Market is not halted")]
+    MarketNotHalted,
+    #[msg("Market is closed")]
+    MarketClosed,
+    #[msg("Outcome mints have already been created")]
+    MintsAlreadyCreated,
+    #[msg("Outcome mints have not been created yet")]
+    MintsNotReady,
+    #[msg("Outcome tokens are still outstanding")]
+    OutstandingTokens,
+    #[msg("Account is not a market in a known layout")]
+    InvalidMarketAccount,
+    #[msg("Market is already at the current version")]
+    MarketAlreadyMigrated,
+    #[msg("Invalid program config")]
+    InvalidConfig,
+    #[msg("Operation is paused")]
+    OperationPaused,
+    #[msg("Invalid pause flags")]
+    InvalidPauseFlags,
+    #[msg("Insufficient funds")]
+    InsufficientFunds,
+    #[msg("Creator fee exceeds the configured maximum")]
+    CreatorFeeTooHigh,
+    #[msg("Invalid fee")]
+    InvalidFee,
+    #[msg("Slippage limit exceeded")]
+    SlippageExceeded,
+    #[msg("Insufficient pool liquidity")]
+    InsufficientLiquidity,
+    #[msg("Market has no LMSR market maker")]
+    LmsrDisabled,
+    #[msg("Amount exceeds what the LMSR has net sold")]
+    ExceedsLmsrNetSold,
+    #[msg("Collateral account required")]
+    MissingCollateralAccount,
+    #[msg("Order price must be between 0 and the collateral per token")]
+    InvalidOrderPrice,
+    #[msg("Order book side is full")]
+    OrderBookFull,
+    #[msg("Order not found")]
+    OrderNotFound,
+    #[msg("Nothing to match")]
+    NothingToMatch,
+    #[msg("Fill event queue is full")]
+    EventQueueFull,
+    #[msg("Invalid open orders account")]
+    InvalidOpenOrders,
+    #[msg("Range markets need lower < upper strike and the default direction")]
+    InvalidStrikeRange,
+    #[msg("Bucket breakpoints must be ascending, start at the strike and give at most MAX_BUCKETS buckets")]
+    InvalidBreakpoints,
+    #[msg("Instruction does not apply to this kind of market")]
+    UnsupportedMarketType,
+    #[msg("Invalid bucket index")]
+    InvalidBucketIndex,
+    #[msg("Bucket mint or token account missing or out of order")]
+    InvalidBucketAccounts,
+    #[msg("Series strikes must be new, ascending and within the batch and series limits")]
+    InvalidSeriesStrikes,
+    #[msg("Market accounts missing, out of order or not in this series")]
+    InvalidSeriesMarket,
+    #[msg("Strike rule needs a positive step and an offset above -100%")]
+    InvalidStrikeRule,
+    #[msg("Roll interval must be positive")]
+    InvalidRollInterval,
+    #[msg("Order is smaller than the order book minimum")]
+    OrderTooSmall,
+    #[msg("LP shares beyond the locked minimum are still outstanding")]
+    LiquidityOutstanding,
 }
@@ -1,539 +1,3328 @@
-use anchor_lang::{
-    prelude::*,
-    solana_program::program::invoke_signed,
-    solana_program::system_instruction,
-    solana_program::pubkey::Pubkey,
-};
+use anchor_lang::prelude::*;
 
-use anchor_spl::metadata::mpl_token_metadata::types::DataV2;
-use anchor_spl::token_2022::spl_token_2022::extension::token_metadata;
-use anchor_spl::token_2022::spl_token_2022::solana_zk_token_sdk::zk_token_proof_program;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
 // use SolanaPriceAccount::account_to_feed;
-use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use pyth_solana_receiver_sdk::price_update::{ PriceUpdateV2, VerificationLevel, Price };
 use pyth_solana_receiver_sdk::price_update::get_feed_id_from_hex;
 use crate::state::*;
 use crate::error::ErrorCode;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    metadata::{ create_metadata_accounts_v3, Metadata, CreateMetadataAccountsV3 },
-    token::{ self, MintTo, Burn, Transfer, CloseAccount },
+    associated_token,
+    token_2022::{
+        spl_token_2022::{
+            self,
+            extension::{
+                transfer_fee::TransferFeeConfig,
+                BaseStateWithExtensions,
+                ExtensionType,
+                StateWithExtensions,
+            },
+        },
+        Token2022,
+    },
+    token_interface::{
+        self,
+        spl_pod::optional_keys::OptionalNonZeroPubkey,
+        spl_token_metadata_interface::state::TokenMetadata,
+        token_metadata_initialize,
+        Burn,
+        CloseAccount,
+        InitializeMint2,
+        MetadataPointerInitialize,
+        Mint,
+        MintTo,
+        TokenAccount,
+        TokenInterface,
+        TokenMetadataInitialize,
+        TransferChecked,
+    },
 };
-use mpl_token_metadata::types::DataV2 as MetaDataV2;
-use mpl_token_metadata::accounts::MasterEdition;
-
-use mpl_token_metadata::ID as METAPLEX_PROGRAM_ID;
 
 pub fn resolve_market(ctx: Context<ResolveMarket>) -> Result<()> {
     let market = &mut ctx.accounts.market;
-
-    // Ensure the market has not already been resolved
-    if market.resolved {
-        msg!("Market is already resolved.");
-        return Err(ErrorCode::MarketAlreadyResolved.into());
-    }
-    // Ensure the market has expired
+    ctx.accounts.config.require_not_paused(market, PAUSE_RESOLVE)?;
+    // Series markets all settle on the series' one observation
+    require!(market.series.is_none(), ErrorCode::UnsupportedMarketType);
     let current_time = Clock::get()?.unix_timestamp;
-    if current_time < market.expiry {
-        msg!(
-            "Market has not expired yet. Current time: {}, Expiry: {}",
-            current_time,
-            market.expiry
-        );
-        return Err(ErrorCode::MarketNotExpired.into());
+
+    // Ensure the market has expired and has not already been resolved
+    market.refresh_status(current_time);
+    match market.status {
+        MarketStatus::Expired => {}
+        MarketStatus::Resolved | MarketStatus::Voided => {
+            msg!("Market is already resolved.");
+            return Err(ErrorCode::MarketAlreadyResolved.into());
+        }
+        MarketStatus::Open => {
+            msg!(
+                "Market has not expired yet. Current time: {}, Expiry: {}",
+                current_time,
+                market.expiry
+            );
+            return Err(ErrorCode::MarketNotExpired.into());
+        }
+        _ => market.require_status(&[MarketStatus::Expired], ErrorCode::MarketNotOpen)?,
     }
+    let signer = ctx.accounts.signer.key();
+    let path = resolution_path(
+        market.resolver,
+        &signer,
+        market.expiry,
+        ctx.accounts.config.resolver_grace_period,
+        current_time
+    )?;
+
     msg!("Resolving market...");
-    msg!("Strike price is {}", market.strike);
+    msg!("Strike price is {} * 10^{}", market.strike, market.strike_exponent);
 
     // Fetch price for the associated asset
-    msg!("Fetching price for asset: {}", market.asset);
-    let price = match market.asset {
-        1 => fetch_btc_price(&ctx.accounts.price_account)?, // BTC
-        2 => fetch_sol_price(&ctx.accounts.price_account)?, // SOL
-        3 => fetch_eth_price(&ctx.accounts.price_account)?, // ETH
-        _ => {
-            msg!("Invalid asset type: {}", market.asset);
-            return Err(ErrorCode::InvalidAsset.into());
-        }
+    msg!("Fetching price for asset: {}", ctx.accounts.asset_config.symbol);
+    let asset_config = &ctx.accounts.asset_config;
+    let oracle_price = fetch_settlement_price(
+        &ctx.accounts.price_account,
+        asset_config,
+        market.expiry
+    )?;
+    let price = oracle_price.price;
+    msg!("Fetched price: {} * 10^{}", price.mantissa, price.exponent);
+
+    let Some(outcome) = settlement_outcome(market, &oracle_price, asset_config)? else {
+        return Err(ErrorCode::PriceTooCloseToStrike.into());
     };
-    msg!("Fetched price: {}", price);
-    // Determine the outcome based on the strike price
-    if price >= (market.strike as f64) {
-        market.outcome = Some(1); // "Yes"
-        msg!("Outcome: Yes (price >= strike)");
+    record_settlement(market, outcome, &oracle_price, path, signer);
+    Ok(())
+}
+
+/// Outcome `oracle_price` settles `market` on, or `None` when the confidence
+/// band straddles a strike and the market's confidence mode says to retry
+/// with a later update.
+fn settlement_outcome(
+    market: &mut Market,
+    oracle_price: &OraclePrice,
+    asset_config: &AssetConfig
+) -> Result<Option<Outcome>> {
+    let price = oracle_price.price;
+    let outcome = if market.bucket_count > 0 {
+        // No bucket is a safe default, so a band straddling any breakpoint must retry
+        for breakpoint in market.bucket_breakpoints() {
+            let breakpoint = FixedPrice::new(*breakpoint as i128, market.strike_exponent);
+            if is_within_confidence_band(oracle_price, &breakpoint, asset_config.strike_confidence_k_bps)? {
+                msg!("Price is within k * conf of a breakpoint, retry with a later update");
+                return Ok(None);
+            }
+        }
+        market.winning_bucket = market.bucket_for(&price)?;
+        msg!("Outcome: bucket {} ({})", market.winning_bucket, market.bucket_label(market.winning_bucket));
+        Outcome::Yes
     } else {
-        market.outcome = Some(2); // "No"
-        msg!("Outcome: No (price < strike)");
-    }
+        let strike = market.strike_price();
+        let (yes, near_strike) = match market.range {
+            None =>
+                (
+                    market.direction.is_yes(price.checked_cmp(&strike)?),
+                    is_within_confidence_band(oracle_price, &strike, asset_config.strike_confidence_k_bps)?,
+                ),
+            Some(range) => {
+                let upper = FixedPrice::new(range.upper_strike as i128, market.strike_exponent);
+                (
+                    range.contains(price.checked_cmp(&strike)?, price.checked_cmp(&upper)?),
+                    is_within_confidence_band(oracle_price, &strike, asset_config.strike_confidence_k_bps)? ||
+                        is_within_confidence_band(oracle_price, &upper, asset_config.strike_confidence_k_bps)?,
+                )
+            }
+        };
+
+        // Guard against settling on a coin flip when the confidence band straddles a strike
+        let Some(yes) = market.confidence_mode.guard(yes, near_strike) else {
+            msg!("Price is within k * conf of the strike, retry with a later update");
+            return Ok(None);
+        };
+
+        // Determine the outcome based on the strike price, compared exactly in integer space
+        if yes {
+            match market.range {
+                None => msg!("Outcome: Yes (price {} strike)", market.direction.label()),
+                Some(range) => msg!("Outcome: Yes (price in {})", range.label(market.strike)),
+            }
+            Outcome::Yes
+        } else {
+            msg!("Outcome: No");
+            Outcome::No
+        }
+    };
+    Ok(Some(outcome))
+}
 
-    // Mark the market as resolved
-    market.resolved = true;
-    msg!("Market resolved successfully with outcome: {:?}", market.outcome);
+/// Marks an expired market resolved on `outcome` and records the settlement.
+fn record_settlement(
+    market: &mut Account<Market>,
+    outcome: Outcome,
+    oracle_price: &OraclePrice,
+    path: ResolutionPath,
+    resolved_by: Pubkey
+) {
+    market.outcome = Some(outcome);
+
+    // Record what the market settled on for audit
+    market.settlement_price = oracle_price.price;
+    market.settlement_conf = oracle_price.conf;
+    market.settlement_publish_time = oracle_price.publish_time;
 
-    Ok(())
+    // Mark the market as resolved
+    market.status = MarketStatus::Resolved;
+    msg!("Market resolved successfully with outcome: {:?}", outcome);
+
+    emit!(MarketResolved {
+        market: market.key(),
+        outcome,
+        price: oracle_price.price,
+        publish_time: oracle_price.publish_time,
+        path,
+        resolved_by,
+        winning_bucket: (market.bucket_count > 0).then_some(market.winning_bucket),
+    });
 }
 
-const ADMIN_KEY: &str = "EJZQiTeikeg8zgU7YgRfwZCxc9GdhTsYR3fQrXv3uK9V";
-const LAMPORTS_PER_TOKEN: u64 = 100_000;
-
-pub fn lock_funds(ctx: Context<LockFunds>, amount: u64) -> Result<()> {
-    let lamports_to_lock = amount * LAMPORTS_PER_TOKEN;
-    let market_seeds = &[
-        b"market",
-        ctx.accounts.market.authority.as_ref(),
-        &ctx.accounts.market.strike.to_le_bytes(),
-        &ctx.accounts.market.expiry.to_le_bytes(),
-        &[ctx.bumps.market],
-    ];
-    let signer_seeds = &[&market_seeds[..]];
+/// During the grace period after expiry only the designated resolver may settle.
+fn resolution_path(
+    resolver: Option<Pubkey>,
+    signer: &Pubkey,
+    expiry: i64,
+    grace_period: i64,
+    now: i64
+) -> Result<ResolutionPath> {
+    match resolver {
+        Some(resolver) if resolver == *signer => Ok(ResolutionPath::Resolver),
+        Some(_) => {
+            let grace_end = expiry.checked_add(grace_period).ok_or(ErrorCode::Overflow)?;
+            require!(now >= grace_end, ErrorCode::Unauthorized);
+            Ok(ResolutionPath::Permissionless)
+        }
+        None => Ok(ResolutionPath::Permissionless),
+    }
+}
 
-    // ✅ Use invoke_signed to transfer lamports
-    invoke_signed(
-        &system_instruction::transfer(
-            &ctx.accounts.user.key(),
-            &ctx.accounts.market.key(),
-            lamports_to_lock
-        ),
-        &[
-            ctx.accounts.user.to_account_info(),
-            ctx.accounts.market.to_account_info(),
-            ctx.accounts.system_program.to_account_info(),
-        ],
-        signer_seeds // ✅ Sign the transaction with the market PDA seeds
+/// Admin fallback for markets the oracle never produced a valid settlement
+/// price for. Only available `config.force_resolve_timeout` seconds after expiry.
+pub fn force_resolve(ctx: Context<AdminMarket>, outcome: Outcome, price: FixedPrice) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let current_time = Clock::get()?.unix_timestamp;
+    market.refresh_status(current_time);
+    market.require_status(
+        &[MarketStatus::Expired, MarketStatus::Halted],
+        ErrorCode::MarketAlreadyResolved
     )?;
 
-    msg!(
-        "User locked {} lamports and received {} YES and NO tokens each.",
-        lamports_to_lock,
-        amount
-    );
-
-    // ✅ Transfer YES tokens
-    let yes_transfer_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: ctx.accounts.treasury_yes_token_account.to_account_info(),
-            to: ctx.accounts.user_yes_token_account.to_account_info(),
-            authority: ctx.accounts.market.to_account_info(),
-        },
-        signer_seeds
-    );
-    token::transfer(yes_transfer_ctx, amount)?;
+    let timeout_end = market.expiry.checked_add(ctx.accounts.config.force_resolve_timeout).ok_or(ErrorCode::Overflow)?;
+    require!(current_time >= timeout_end, ErrorCode::ForceResolveTooEarly);
 
-    // ✅ Transfer NO tokens
-    let no_transfer_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: ctx.accounts.treasury_no_token_account.to_account_info(),
-            to: ctx.accounts.user_no_token_account.to_account_info(),
-            authority: ctx.accounts.market.to_account_info(),
-        },
-        signer_seeds
-    );
-    token::transfer(no_transfer_ctx, amount)?;
+    // Bucket markets settle `Yes` on the bucket `price` falls in, never `No`
+    if market.bucket_count > 0 {
+        require!(outcome != Outcome::No, ErrorCode::UnsupportedMarketType);
+        market.winning_bucket = market.bucket_for(&price)?;
+    }
+    market.outcome = Some(outcome);
+    market.settlement_price = price;
+    market.settlement_conf = 0;
+    market.settlement_publish_time = current_time;
+    market.status = match outcome {
+        Outcome::Void => MarketStatus::Voided,
+        Outcome::Yes | Outcome::No => MarketStatus::Resolved,
+    };
+    msg!("⚠️ Market force-resolved by admin with outcome: {:?}", outcome);
+
+    emit!(MarketResolved {
+        market: market.key(),
+        outcome,
+        price,
+        publish_time: current_time,
+        path: ResolutionPath::ForceResolve,
+        resolved_by: ctx.accounts.admin.key(),
+        winning_bucket: (market.bucket_count > 0 && outcome == Outcome::Yes).then_some(market.winning_bucket),
+    });
 
     Ok(())
 }
 
-pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
+/// Admin cancellation of a market before anyone holds a position (e.g.
+/// mis-configured feed): not yet open, or open with no YES/NO minted. Once
+/// tokens exist the market can only be voided through `force_resolve` after
+/// the resolution timeout.
+pub fn cancel_market(ctx: Context<CancelMarket>) -> Result<()> {
     let market = &mut ctx.accounts.market;
-    // let market_authority = &mut ctx.accounts.market_authority;
-    let user = &ctx.accounts.user;
-    let token_program = &ctx.accounts.token_program;
-
-    // ✅ Ensure the market has been resolved
-    require!(market.resolved, ErrorCode::MarketNotResolved);
+    let current_time = Clock::get()?.unix_timestamp;
+    market.refresh_status(current_time);
+    market.require_status(
+        &[MarketStatus::Created, MarketStatus::MintsReady, MarketStatus::Open],
+        ErrorCode::MarketAlreadyResolved
+    )?;
+    if market.status == MarketStatus::Open {
+        // Bucket supplies are spread over up to MAX_BUCKETS mints; only
+        // cancel those before they open
+        require!(market.bucket_count == 0, ErrorCode::UnsupportedMarketType);
+        let (Some(yes_mint), Some(no_mint)) = (&ctx.accounts.yes_mint, &ctx.accounts.no_mint) else {
+            return Err(ErrorCode::MintsNotReady.into());
+        };
+        require!(yes_mint.supply == 0 && no_mint.supply == 0, ErrorCode::OutstandingTokens);
+    }
 
-    // ✅ Determine which token should be burned and redeemed
-    let (user_token_account, treasury_token_account, mint) = match market.outcome {
-        Some(1) => {
-            msg!("✅ Market outcome is YES. Burning all YES tokens.");
-            (
-                &ctx.accounts.user_yes_token_account,
-                &ctx.accounts.treasury_yes_token_account,
-                &ctx.accounts.yes_mint,
-            )
-        }
-        Some(2) => {
-            msg!("✅ Market outcome is NO. Burning all NO tokens.");
-            (
-                &ctx.accounts.user_no_token_account,
-                &ctx.accounts.treasury_no_token_account,
-                &ctx.accounts.no_mint,
-            )
-        }
-        _ => {
-            return Err(ErrorCode::MarketNotResolved.into());
-        }
-    };
+    market.outcome = Some(Outcome::Void);
+    market.settlement_publish_time = current_time;
+    market.status = MarketStatus::Voided;
+    msg!("⚠️ Market cancelled by admin, outcome is void");
+
+    emit!(MarketResolved {
+        market: market.key(),
+        outcome: Outcome::Void,
+        price: market.settlement_price,
+        publish_time: current_time,
+        path: ResolutionPath::Cancelled,
+        resolved_by: ctx.accounts.admin.key(),
+        winning_bucket: None,
+    });
 
-    // ✅ Fetch the user's token balance
-    let user_token_balance = user_token_account.amount;
-    require!(user_token_balance > 0, ErrorCode::InsufficientTokens);
+    Ok(())
+}
 
-    let total_lamports = user_token_balance
-        .checked_mul(LAMPORTS_PER_TOKEN)
-        .ok_or(ErrorCode::Overflow)?;
+/// Stops an open (or expired, unresolved) market. Admin only.
+pub fn halt_market(ctx: Context<AdminMarket>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.require_status(&[MarketStatus::Open, MarketStatus::Expired], ErrorCode::MarketNotOpen)?;
+    market.status = MarketStatus::Halted;
+    msg!("⚠️ Market halted by admin");
+    Ok(())
+}
 
-    // ✅ Burn all user's tokens
-    let cpi_accounts = Burn {
-        mint: mint.to_account_info(),
-        from: user_token_account.to_account_info(),
-        authority: user.to_account_info(),
-    };
-    let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
-    token::burn(cpi_ctx, user_token_balance)?;
+/// Resumes a halted market. Admin only.
+pub fn resume_market(ctx: Context<AdminMarket>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    require!(market.status == MarketStatus::Halted, ErrorCode::MarketNotHalted);
+    market.status = MarketStatus::Open;
+    market.refresh_status(Clock::get()?.unix_timestamp);
+    msg!("✅ Market resumed, now {:?}", market.status);
+    Ok(())
+}
 
-    msg!(
-        "✅ Burned {} tokens for user. Transferring {} lamports...",
-        user_token_balance,
-        total_lamports
-    );
-
-    // ✅ Transfer lamports from Market PDA to the user
-    let market_seeds = &[
-        b"market",
-        market.authority.as_ref(),
-        &market.strike.to_le_bytes(),
-        &market.expiry.to_le_bytes(),
-        &[ctx.bumps.market],
-    ];
-    let signer = &[&market_seeds[..]];
-    **ctx.accounts.market.to_account_info().try_borrow_mut_lamports()? -= total_lamports;
-    **ctx.accounts.user.try_borrow_mut_lamports()? += total_lamports;
-    msg!(
-        "✅ Successfully redeemed {} tokens and transferred {} lamports to user",
-        user_token_balance,
-        total_lamports
+/// Opens a market whose outcome mints are ready. Market creator only.
+pub fn open_market(ctx: Context<MarketAuthority>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.require_status(&[MarketStatus::MintsReady], ErrorCode::MintsNotReady)?;
+    require!(
+        Clock::get()?.unix_timestamp < market.expiry,
+        ErrorCode::MarketAlreadyExpired
     );
-
-    // Close the user's token account to reclaim rent
-    let close_cpi_accounts = CloseAccount {
-        account: user_token_account.to_account_info(),
-        destination: user.to_account_info(),
-        authority: user.to_account_info(),
-    };
-    let close_cpi_ctx = CpiContext::new(token_program.to_account_info(), close_cpi_accounts);
-    token::close_account(close_cpi_ctx)?;
-
-    msg!("✅ Closed user's token account to reclaim rent.");
-
+    market.status = MarketStatus::Open;
+    msg!("✅ Market open for minting");
     Ok(())
 }
 
-pub const MAXIMUM_AGE: u64 = 3600; // 1 hour
-pub const FEED_ID: &str = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
-pub const STALENESS_THRESHOLD: u64 = 120; // staleness threshold in seconds
+/// Pays the creator fees accrued in the market vault to the market authority.
+pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>) -> Result<()> {
+    let market = &ctx.accounts.market;
+    let amount = market.creator_fees_accrued;
+    require!(amount > 0, ErrorCode::InvalidAmount);
 
-//Doesnt work
-pub fn get_price_feed(ctx: Context<GetPriceFeed>, feed_id_str: String) -> Result<f64> {
-    msg!("get_price_feed: feed_id_str: {}", feed_id_str);
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+    let claim_ctx = CpiContext::new_with_signer(
+        ctx.accounts.collateral_token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.collateral_vault.to_account_info(),
+            mint: ctx.accounts.collateral_mint.to_account_info(),
+            to: ctx.accounts.authority_collateral_account.to_account_info(),
+            authority: market.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(claim_ctx, amount, ctx.accounts.collateral_mint.decimals)?;
 
-    let price_update = &mut ctx.accounts.price_update;
+    ctx.accounts.market.creator_fees_accrued = 0;
+    msg!("✅ Creator claimed {} collateral in fees", amount);
+    Ok(())
+}
 
-    let feed_id = get_feed_id_from_hex(&feed_id_str.as_str())?;
-    let price = price_update.get_price_no_older_than(
-        &Clock::get()?,
-        STALENESS_THRESHOLD,
-        &feed_id
+/// Creates the constant-product YES/NO pool for a market. Market creator only.
+pub fn initialize_pool(ctx: Context<InitializePool>, fee_bps: u16) -> Result<()> {
+    require!(fee_bps <= MAX_POOL_FEE_BPS, ErrorCode::InvalidFee);
+    ctx.accounts.market.require_status(
+        &[MarketStatus::MintsReady, MarketStatus::Open],
+        ErrorCode::MintsNotReady
     )?;
 
-    let final_price = (price.price as f64) * (10f64).powi(price.exponent as i32);
-
-    msg!("get_price_feed feed_id {:?}, price is {}", feed_id, final_price);
-
-    Ok(final_price)
+    let pool = &mut ctx.accounts.pool;
+    pool.market = ctx.accounts.market.key();
+    pool.lp_mint = ctx.accounts.lp_mint.key();
+    pool.yes_reserve = 0;
+    pool.no_reserve = 0;
+    pool.fee_bps = fee_bps;
+    pool.bump = ctx.bumps.pool;
+    msg!("✅ Pool initialized with a {} bps swap fee", fee_bps);
+    Ok(())
 }
 
-pub fn fetch_btc_price(price_account: &Account<PriceUpdateV2>) -> Result<f64> {
-    // 1-Fetch latest price
+/// Deposits YES and NO at the pool ratio (any ratio for the first deposit)
+/// and mints LP shares. Deposits never exceed `max_yes`/`max_no`. The first
+/// deposit locks `MINIMUM_LIQUIDITY` of its shares in the pool for good; the
+/// reserves behind them are burned by `redeem_pool_reserves` after settlement.
+pub fn add_liquidity(ctx: Context<PoolLiquidity>, max_yes: u64, max_no: u64, min_lp: u64) -> Result<()> {
+    require_trading_open(&mut ctx.accounts.market, &ctx.accounts.config)?;
+    require!(max_yes > 0 && max_no > 0, ErrorCode::InvalidAmount);
+
+    let pool = &ctx.accounts.pool;
+    let supply = ctx.accounts.lp_mint.supply;
+    let (yes_in, no_in, lp_out, lp_locked) = if supply == 0 {
+        let lp = u64::try_from(isqrt((max_yes as u128) * (max_no as u128))).map_err(|_| ErrorCode::Overflow)?;
+        require!(lp > MINIMUM_LIQUIDITY, ErrorCode::InsufficientLiquidity);
+        (max_yes, max_no, lp - MINIMUM_LIQUIDITY, MINIMUM_LIQUIDITY)
+    } else {
+        let lp = mul_div(max_yes, supply, pool.yes_reserve)?.min(mul_div(max_no, supply, pool.no_reserve)?);
+        (
+            mul_div_ceil(lp, pool.yes_reserve, supply)?,
+            mul_div_ceil(lp, pool.no_reserve, supply)?,
+            lp,
+            0,
+        )
+    };
+    require!(lp_out > 0, ErrorCode::InvalidAmount);
+    require!(lp_out >= min_lp && yes_in <= max_yes && no_in <= max_no, ErrorCode::SlippageExceeded);
 
-    // get_price_no_older_than will fail if the price update is for a different price feed.
-    // This string is the id of the BTC/USD feed. See https://pyth.network/developers/price-feed-ids for all available IDs.
-    let feed_id: [u8; 32] = get_feed_id_from_hex(
-        "e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43"
+    let token_program = &ctx.accounts.token_program;
+    let user = ctx.accounts.user.to_account_info();
+    transfer_tokens(
+        token_program,
+        &ctx.accounts.yes_mint,
+        ctx.accounts.user_yes_token_account.to_account_info(),
+        ctx.accounts.pool_yes_vault.to_account_info(),
+        user.clone(),
+        &[],
+        yes_in
     )?;
-    msg!("btc feed id: {:?}", feed_id);
-
-    let price = price_account.get_price_no_older_than(
-        &Clock::get()?,
-        STALENESS_THRESHOLD,
-        &feed_id
+    transfer_tokens(
+        token_program,
+        &ctx.accounts.no_mint,
+        ctx.accounts.user_no_token_account.to_account_info(),
+        ctx.accounts.pool_no_vault.to_account_info(),
+        user,
+        &[],
+        no_in
     )?;
 
-    let final_price = (price.price as f64) * (10f64).powi(price.exponent as i32);
-
-    msg!("The price is ({} ± {}) * 10^{}", price.price, price.conf, price.exponent);
-    msg!("The price is: {}", final_price);
+    let market_key = ctx.accounts.market.key();
+    let pool_seeds = &[b"pool".as_ref(), market_key.as_ref(), &[pool.bump]];
+    let signer = &[&pool_seeds[..]];
+    for (to, amount) in [
+        (ctx.accounts.user_lp_token_account.to_account_info(), lp_out),
+        (ctx.accounts.pool_lp_vault.to_account_info(), lp_locked),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let mint_lp_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                to,
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            signer
+        );
+        token_interface::mint_to(mint_lp_ctx, amount)?;
+    }
 
-    Ok(final_price)
+    let pool = &mut ctx.accounts.pool;
+    pool.yes_reserve = pool.yes_reserve.checked_add(yes_in).ok_or(ErrorCode::Overflow)?;
+    pool.no_reserve = pool.no_reserve.checked_add(no_in).ok_or(ErrorCode::Overflow)?;
+    msg!("✅ Added {} YES and {} NO for {} LP shares", yes_in, no_in, lp_out);
+    Ok(())
 }
 
-pub fn fetch_sol_price(price_account: &Account<PriceUpdateV2>) -> Result<f64> {
-    msg!("Fetching SOL price...");
-    let feed_id: [u8; 32] = get_feed_id_from_hex(
-        "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d"
-    )?;
-    let price = price_account.get_price_no_older_than(
-        &Clock::get()?,
-        STALENESS_THRESHOLD,
-        &feed_id
-    )?;
-    let final_price = (price.price as f64) * (10f64).powi(price.exponent as i32);
-    msg!("SOL price: {}", final_price);
-    Ok(final_price)
-}
+/// Burns LP shares for a pro-rata share of both reserves. Still available
+/// after expiry so LPs can take their tokens to `redeem`.
+pub fn remove_liquidity(ctx: Context<PoolLiquidity>, lp_amount: u64, min_yes: u64, min_no: u64) -> Result<()> {
+    ctx.accounts.config.require_not_paused(&ctx.accounts.market, PAUSE_TRADE)?;
+    require!(lp_amount > 0, ErrorCode::InvalidAmount);
 
-pub fn fetch_eth_price(price_account: &Account<PriceUpdateV2>) -> Result<f64> {
-    msg!("Fetching ETH price...");
-    let feed_id: [u8; 32] = get_feed_id_from_hex(
-        "ff61491a931112ddf1bd8147cd1b641375f79f5825126d665480874634fd0ace"
+    let pool = &ctx.accounts.pool;
+    let supply = ctx.accounts.lp_mint.supply;
+    let yes_out = mul_div(lp_amount, pool.yes_reserve, supply)?;
+    let no_out = mul_div(lp_amount, pool.no_reserve, supply)?;
+    require!(yes_out >= min_yes && no_out >= min_no, ErrorCode::SlippageExceeded);
+
+    let token_program = &ctx.accounts.token_program;
+    let burn_ctx = CpiContext::new(token_program.to_account_info(), Burn {
+        mint: ctx.accounts.lp_mint.to_account_info(),
+        from: ctx.accounts.user_lp_token_account.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    });
+    token_interface::burn(burn_ctx, lp_amount)?;
+
+    let market_key = ctx.accounts.market.key();
+    let pool_seeds = &[b"pool".as_ref(), market_key.as_ref(), &[pool.bump]];
+    let signer = &[&pool_seeds[..]];
+    let pool_info = ctx.accounts.pool.to_account_info();
+    transfer_tokens(
+        token_program,
+        &ctx.accounts.yes_mint,
+        ctx.accounts.pool_yes_vault.to_account_info(),
+        ctx.accounts.user_yes_token_account.to_account_info(),
+        pool_info.clone(),
+        signer,
+        yes_out
     )?;
-    let price = price_account.get_price_no_older_than(
-        &Clock::get()?,
-        STALENESS_THRESHOLD,
-        &feed_id
+    transfer_tokens(
+        token_program,
+        &ctx.accounts.no_mint,
+        ctx.accounts.pool_no_vault.to_account_info(),
+        ctx.accounts.user_no_token_account.to_account_info(),
+        pool_info,
+        signer,
+        no_out
     )?;
-    let final_price = (price.price as f64) * (10f64).powi(price.exponent as i32);
-    msg!("ETH price: {}", final_price);
-    Ok(final_price)
-}
 
-pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
-    if ctx.accounts.authority.key.to_string() != ADMIN_KEY {
-        return Err(ErrorCode::Unauthorized.into());
-    }
-    msg!("Initializing treasury account...");
+    let pool = &mut ctx.accounts.pool;
+    pool.yes_reserve = pool.yes_reserve.checked_sub(yes_out).ok_or(ErrorCode::Overflow)?;
+    pool.no_reserve = pool.no_reserve.checked_sub(no_out).ok_or(ErrorCode::Overflow)?;
+    msg!("✅ Burned {} LP shares for {} YES and {} NO", lp_amount, yes_out, no_out);
     Ok(())
 }
 
-pub fn initialize_market(
-    ctx: Context<InitializeMarket>,
-    strike: u64,
-    expiry: i64,
-    asset: u8
-) -> Result<()> {
-    let market = &mut ctx.accounts.market;
-    market.authority = ctx.accounts.authority.key();
-    market.strike = strike;
-    market.expiry = expiry;
-    market.asset = asset;
-    market.resolved = false;
-    market.outcome = None;
+/// Burns the reserves left in a settled market's pool once every LP has
+/// withdrawn, leaving only the `MINIMUM_LIQUIDITY` shares locked on the first
+/// deposit. Nobody can withdraw those reserves, and without this their supply
+/// would keep `close_market` from closing the market. Permissionless.
+pub fn redeem_pool_reserves(ctx: Context<PoolReserves>) -> Result<()> {
+    ctx.accounts.market.require_status(
+        &[MarketStatus::Resolved, MarketStatus::Voided],
+        ErrorCode::MarketNotResolved
+    )?;
+    require!(
+        ctx.accounts.lp_mint.supply == ctx.accounts.pool_lp_vault.amount,
+        ErrorCode::LiquidityOutstanding
+    );
 
-    msg!("Market initialized!");
+    let market_key = ctx.accounts.market.key();
+    let pool_seeds = &[b"pool".as_ref(), market_key.as_ref(), &[ctx.accounts.pool.bump]];
+    let signer = &[&pool_seeds[..]];
+    // Burn the vault balances rather than the recorded reserves, so tokens
+    // sent to the pool directly cannot keep the market open either
+    let yes_burned = ctx.accounts.pool_yes_vault.amount;
+    let no_burned = ctx.accounts.pool_no_vault.amount;
+    for (mint, vault, amount) in [
+        (&ctx.accounts.yes_mint, &ctx.accounts.pool_yes_vault, yes_burned),
+        (&ctx.accounts.no_mint, &ctx.accounts.pool_no_vault, no_burned),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let burn_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: mint.to_account_info(),
+                from: vault.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            signer
+        );
+        token_interface::burn(burn_ctx, amount)?;
+    }
 
-    msg!("Next step: call CreateOutcomeTokens ");
+    let pool = &mut ctx.accounts.pool;
+    pool.yes_reserve = 0;
+    pool.no_reserve = 0;
+    msg!("✅ Burned {} YES and {} NO left in the pool", yes_burned, no_burned);
     Ok(())
 }
 
-#[inline(never)]
-pub fn initialize_outcome_mints(ctx: Context<InitializeOutcomeMints>) -> Result<()> {
-    let market = &ctx.accounts.market;
-    require!(market.resolved == false, ErrorCode::MarketAlreadyResolved);
-
-    //Normally can't mint is deterministic so they're can't be infinitely minted ?
+/// Sells exactly `amount_in` of `side_in` to the pool for at least `min_out`
+/// of the other side.
+pub fn swap_exact_in(ctx: Context<PoolSwap>, side_in: Side, amount_in: u64, min_out: u64) -> Result<()> {
+    require_trading_open(&mut ctx.accounts.market, &ctx.accounts.config)?;
+    require!(amount_in > 0, ErrorCode::InvalidAmount);
 
-    msg!("✅ YES and NO Mints Created!");
-    Ok(())
-}
+    let (reserve_in, reserve_out) = ctx.accounts.pool.reserves(side_in);
+    let amount_out = cp_amount_out(amount_in, reserve_in, reserve_out, ctx.accounts.pool.fee_bps)?;
+    require!(amount_out > 0, ErrorCode::InsufficientLiquidity);
+    require!(amount_out >= min_out, ErrorCode::SlippageExceeded);
 
-//This isn't used currently
-#[inline(never)]
-pub fn initialize_treasury_token_accounts(
-    _ctx: Context<InitializeTreasuryTokenAccounts>
-) -> Result<()> {
-    msg!("✅ Treasury Token Accounts Initialized!");
-    Ok(())
+    execute_swap(ctx, side_in, amount_in, amount_out)
 }
 
-#[inline(never)]
-pub fn mint_outcome_tokens(ctx: Context<MintOutcomeTokens>) -> Result<()> {
-    let token_program = &ctx.accounts.token_program;
-
-    if ctx.accounts.yes_mint.mint_authority != Some(ctx.accounts.market.key()).into() {
-        return Err(ErrorCode::InvalidMintAccount.into());
-    }
+/// Buys exactly `amount_out` of the side opposite `side_in` from the pool,
+/// paying at most `max_in` of `side_in`.
+pub fn swap_exact_out(ctx: Context<PoolSwap>, side_in: Side, amount_out: u64, max_in: u64) -> Result<()> {
+    require_trading_open(&mut ctx.accounts.market, &ctx.accounts.config)?;
+    require!(amount_out > 0, ErrorCode::InvalidAmount);
 
-    let market_key = &ctx.accounts.market.authority.key();
+    let (reserve_in, reserve_out) = ctx.accounts.pool.reserves(side_in);
+    let amount_in = cp_amount_in(amount_out, reserve_in, reserve_out, ctx.accounts.pool.fee_bps)?;
+    require!(amount_in <= max_in, ErrorCode::SlippageExceeded);
 
-    let market_seeds = &[
-        b"market",
-        market_key.as_ref(),
-        &ctx.accounts.market.strike.to_le_bytes(),
-        &ctx.accounts.market.expiry.to_le_bytes(),
-        &[ctx.bumps.market],
-    ];
-    let signer = &[&market_seeds[..]];
+    execute_swap(ctx, side_in, amount_in, amount_out)
+}
 
-    // ✅ Mint 500,000 YES Tokens
-    // let binding: [&[&[u8]];1] = [market_seeds];
-    let yes_mint_ctx = CpiContext::new_with_signer(
-        token_program.to_account_info(),
-        MintTo {
-            mint: ctx.accounts.yes_mint.to_account_info(),
-            to: ctx.accounts.treasury_yes_token_account.to_account_info(),
-            authority: ctx.accounts.market.to_account_info(),
-        },
-        signer
-    );
+/// Buys `amount` of `side` from the market's LMSR market maker for at most
+/// `max_cost` collateral, creator fee included.
+pub fn lmsr_buy(ctx: Context<LmsrTrade>, side: Side, amount: u64, max_cost: u64) -> Result<()> {
+    require!(ctx.accounts.market.lmsr_b > 0, ErrorCode::LmsrDisabled);
+    require_trading_open(&mut ctx.accounts.market, &ctx.accounts.config)?;
+    require!(amount > 0, ErrorCode::InvalidAmount);
 
-    token::mint_to(yes_mint_ctx, 500_000)?;
+    let market = &ctx.accounts.market;
+    let (q_yes, q_no) = match side {
+        Side::Yes => (market.lmsr_q_yes.checked_add(amount).ok_or(ErrorCode::Overflow)?, market.lmsr_q_no),
+        Side::No => (market.lmsr_q_yes, market.lmsr_q_no.checked_add(amount).ok_or(ErrorCode::Overflow)?),
+    };
+    let cost_before = lmsr_cost(market.lmsr_b, market.lmsr_q_yes, market.lmsr_q_no)?;
+    let cost_after = lmsr_cost(market.lmsr_b, q_yes, q_no)?;
+    let cost = wad_to_collateral(cost_after - cost_before, market.collateral_per_token, true)?;
+    let creator_fee = bps_of(cost, market.creator_fee_bps)?;
+    let total_cost = cost.checked_add(creator_fee).ok_or(ErrorCode::Overflow)?;
+    require!(total_cost <= max_cost, ErrorCode::SlippageExceeded);
+
+    deposit_collateral(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.collateral_mint,
+        ctx.accounts.user_collateral_account.to_account_info(),
+        &mut ctx.accounts.collateral_vault,
+        ctx.accounts.user.to_account_info(),
+        total_cost
+    )?;
 
-    // ✅ Mint 500,000 NO Tokens
-    let no_mint_ctx = CpiContext::new_with_signer(
-        token_program.to_account_info(),
+    let (mint, user_token_account) = match side {
+        Side::Yes => (&ctx.accounts.yes_mint, &ctx.accounts.user_yes_token_account),
+        Side::No => (&ctx.accounts.no_mint, &ctx.accounts.user_no_token_account),
+    };
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+    let mint_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
         MintTo {
-            mint: ctx.accounts.no_mint.to_account_info(),
-            to: ctx.accounts.treasury_no_token_account.to_account_info(),
-            authority: ctx.accounts.market.to_account_info(),
+            mint: mint.to_account_info(),
+            to: user_token_account.to_account_info(),
+            authority: market.to_account_info(),
         },
         signer
     );
-    token::mint_to(no_mint_ctx, 500_000)?;
+    token_interface::mint_to(mint_ctx, amount)?;
 
-    msg!("✅ 500,000 YES and NO tokens successfully minted!");
+    let market = &mut ctx.accounts.market;
+    market.lmsr_q_yes = q_yes;
+    market.lmsr_q_no = q_no;
+    market.creator_fees_accrued = market.creator_fees_accrued
+        .checked_add(creator_fee)
+        .ok_or(ErrorCode::Overflow)?;
+    msg!("✅ Bought {} {:?} from the LMSR for {} collateral (creator fee {})", amount, side, cost, creator_fee);
     Ok(())
 }
 
-pub fn create_mint(
-    ctx: Context<CreateMint>,
-) -> Result<()> {
-    
-    let market_key = &ctx.accounts.market.key();
-    let bump_yes=&[ ctx.bumps.yes_mint];
-    let seeds_yes = &["yes_mint".as_bytes(), market_key.as_ref(), bump_yes];
-    let signer_yes:&[&[&[u8]]] = &[&seeds_yes[..]];
-
-    let cpi_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_metadata_program.to_account_info(),
-        CreateMetadataAccountsV3 {
-            metadata: ctx.accounts.yes_metadata_account.to_account_info(), // the metadata account being created
-            mint: ctx.accounts.yes_mint.to_account_info(), // the mint account of the metadata account
-            mint_authority: ctx.accounts.yes_mint.to_account_info(), // the mint authority of the mint account
-            update_authority: ctx.accounts.yes_mint.to_account_info(), // the update authority of the metadata account
-            payer: ctx.accounts.authority.to_account_info(), // the payer for creating the metadata account
-            system_program: ctx.accounts.system_program.to_account_info(), // the system program account
-            rent: ctx.accounts.rent.to_account_info(), // the rent sysvar account
-        },
-        signer_yes
-    );
+/// Sells `amount` of `side` back to the LMSR for at least `min_proceeds`.
+/// Only tokens the LMSR has net sold can be sold back to it.
+pub fn lmsr_sell(ctx: Context<LmsrTrade>, side: Side, amount: u64, min_proceeds: u64) -> Result<()> {
+    require!(ctx.accounts.market.lmsr_b > 0, ErrorCode::LmsrDisabled);
+    require_trading_open(&mut ctx.accounts.market, &ctx.accounts.config)?;
+    require!(amount > 0, ErrorCode::InvalidAmount);
 
-    
     let market = &ctx.accounts.market;
-    let asset_name = match market.asset {
-        1 => "BTC",
-        2=> "SOL",
-        3=>"ETH",
-        _=>"INVALID"
+    let (q_yes, q_no) = match side {
+        Side::Yes => (market.lmsr_q_yes.checked_sub(amount).ok_or(ErrorCode::ExceedsLmsrNetSold)?, market.lmsr_q_no),
+        Side::No => (market.lmsr_q_yes, market.lmsr_q_no.checked_sub(amount).ok_or(ErrorCode::ExceedsLmsrNetSold)?),
     };
-    let symbol_yes = "YES".to_string();
-    let name_yes=   format!("{}_{}_EXP:{}_{}", asset_name, market.strike, market.expiry, symbol_yes);
-    let uri = "*".to_string();
-    
-    create_metadata_accounts_v3(
-        cpi_ctx, // cpi context
-        DataV2 {
-            name: name_yes,
-            symbol: symbol_yes,
-            uri: uri,
-            seller_fee_basis_points: 0,
-            creators: None,
-            collection: None,
-            uses: None,
-        }, // token metadata
-        false, // is_mutable
-        false, // update_authority_is_signer
-        None // collection details
-    )?;
+    let cost_before = lmsr_cost(market.lmsr_b, market.lmsr_q_yes, market.lmsr_q_no)?;
+    let cost_after = lmsr_cost(market.lmsr_b, q_yes, q_no)?;
+    let proceeds = wad_to_collateral(cost_before - cost_after, market.collateral_per_token, false)?;
+    require!(proceeds >= min_proceeds, ErrorCode::SlippageExceeded);
+
+    let (mint, user_token_account) = match side {
+        Side::Yes => (&ctx.accounts.yes_mint, &ctx.accounts.user_yes_token_account),
+        Side::No => (&ctx.accounts.no_mint, &ctx.accounts.user_no_token_account),
+    };
+    let burn_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), Burn {
+        mint: mint.to_account_info(),
+        from: user_token_account.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    });
+    token_interface::burn(burn_ctx, amount)?;
 
-    //For the no token
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+    if proceeds > 0 {
+        let payout_ctx = CpiContext::new_with_signer(
+            ctx.accounts.collateral_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.collateral_vault.to_account_info(),
+                mint: ctx.accounts.collateral_mint.to_account_info(),
+                to: ctx.accounts.user_collateral_account.to_account_info(),
+                authority: market.to_account_info(),
+            },
+            signer
+        );
+        token_interface::transfer_checked(payout_ctx, proceeds, ctx.accounts.collateral_mint.decimals)?;
+    }
+
+    let market = &mut ctx.accounts.market;
+    market.lmsr_q_yes = q_yes;
+    market.lmsr_q_no = q_no;
+    msg!("✅ Sold {} {:?} to the LMSR for {} collateral", amount, side, proceeds);
+    Ok(())
+}
 
-    let bump_no=&[ ctx.bumps.no_mint];
-    let seeds_no = &["no_mint".as_bytes(), market_key.as_ref(), bump_no];
-    let signer_no:&[&[&[u8]]] = &[&seeds_no[..]];
+/// Returns whatever the LMSR subsidy did not lose: everything in the vault
+/// beyond what outcome holders and the creator fee balance are owed.
+pub fn reclaim_lmsr_subsidy(ctx: Context<ReclaimLmsrSubsidy>) -> Result<()> {
+    let market = &ctx.accounts.market;
+    require!(market.lmsr_b > 0, ErrorCode::LmsrDisabled);
+    market.require_status(
+        &[MarketStatus::Resolved, MarketStatus::Voided],
+        ErrorCode::MarketNotResolved
+    )?;
 
-    let cpi_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_metadata_program.to_account_info(),
-        CreateMetadataAccountsV3 {
-            metadata: ctx.accounts.no_metadata_account.to_account_info(), // the metadata account being created
-            mint: ctx.accounts.no_mint.to_account_info(), // the mint account of the metadata account
-            mint_authority: ctx.accounts.no_mint.to_account_info(), // the mint authority of the mint account
-            update_authority: ctx.accounts.no_mint.to_account_info(), // the update authority of the metadata account
-            payer: ctx.accounts.authority.to_account_info(), // the payer for creating the metadata account
-            system_program: ctx.accounts.system_program.to_account_info(), // the system program account
-            rent: ctx.accounts.rent.to_account_info(), // the rent sysvar account
+    let owed = market
+        .settled_liabilities(ctx.accounts.yes_mint.supply, ctx.accounts.no_mint.supply)?
+        .checked_add(market.creator_fees_accrued)
+        .ok_or(ErrorCode::Overflow)?;
+    let surplus = ctx.accounts.collateral_vault.amount.saturating_sub(owed);
+    require!(surplus > 0, ErrorCode::InsufficientFunds);
+
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+    let reclaim_ctx = CpiContext::new_with_signer(
+        ctx.accounts.collateral_token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.collateral_vault.to_account_info(),
+            mint: ctx.accounts.collateral_mint.to_account_info(),
+            to: ctx.accounts.authority_collateral_account.to_account_info(),
+            authority: market.to_account_info(),
         },
-        signer_no,
+        signer
     );
+    token_interface::transfer_checked(reclaim_ctx, surplus, ctx.accounts.collateral_mint.decimals)?;
+    msg!("✅ Creator reclaimed {} collateral of LMSR subsidy", surplus);
+    Ok(())
+}
 
-
-    let symbol_no = "NO".to_string();
-    let name_no=  format!("{}_{}_EXP:{}_{}", asset_name, market.strike, market.expiry, symbol_no);
-    let uri = "*".to_string();
-    
-    create_metadata_accounts_v3(
-        cpi_ctx, // cpi context
-        DataV2 {
-            name: name_no,
-            symbol: symbol_no,
-            uri: uri,
-            seller_fee_basis_points: 0,
-            creators: None,
-            collection: None,
-            uses: None,
-        }, // token metadata
-        false, // is_mutable
-        false, // update_authority_is_signer
-        None // collection details
+/// Creates the order book for a market. Market creator only.
+pub fn initialize_order_book(ctx: Context<InitializeOrderBook>, min_order_size: u64) -> Result<()> {
+    ctx.accounts.market.require_status(
+        &[MarketStatus::MintsReady, MarketStatus::Open],
+        ErrorCode::MintsNotReady
     )?;
-
-    msg!("Succesfully initialized token mint");
+    require!(min_order_size > 0, ErrorCode::InvalidAmount);
+
+    let market_key = ctx.accounts.market.key();
+    let order_book = &mut ctx.accounts.order_book;
+    order_book.market = market_key;
+    order_book.next_order_id = 0;
+    order_book.bids = Vec::new();
+    order_book.asks = Vec::new();
+    order_book.bump = ctx.bumps.order_book;
+    order_book.min_order_size = min_order_size;
+
+    let event_queue = &mut ctx.accounts.event_queue;
+    event_queue.market = market_key;
+    event_queue.events = Vec::new();
+    event_queue.bump = ctx.bumps.event_queue;
+    msg!("✅ Order book initialized, minimum order {} YES", min_order_size);
     Ok(())
 }
 
-pub fn mint_metadata_tokens(ctx: Context<MintMetadataTokens>) -> Result<()> {
-    let token_program = &ctx.accounts.token_program;
-    // let market_seeds = &[
-    //     b"market",
-    //     market_key.as_ref(),
-    //     &ctx.accounts.market.strike.to_le_bytes(),
-    //     &ctx.accounts.market.expiry.to_le_bytes(),
-    //     &[ctx.bumps.market],
-    // ];
-
-    let market_key = &ctx.accounts.market.key();
-    let bump_yes=&[ ctx.bumps.yes_mint];
-    let seeds_yes = &["yes_mint".as_bytes(), market_key.as_ref(), bump_yes];
-    let signer_yes:&[&[&[u8]]] = &[&seeds_yes[..]];
-
-    let yes_mint_ctx = CpiContext::new_with_signer(
-        token_program.to_account_info(),
-        MintTo {
-            mint: ctx.accounts.yes_mint.to_account_info(),
-            to: ctx.accounts.treasury_yes_token_account.to_account_info(),
-            authority: ctx.accounts.yes_mint.to_account_info(),
-        },
-        signer_yes
+/// Rests a limit order on the book, escrowing collateral for a bid or YES for
+/// an ask. Orders only trade once `match_orders` is cranked. On a full side
+/// the order has to beat the worst resting one, which is evicted and credited
+/// to its owner's `OpenOrders` (a remaining account unless it is the user's).
+pub fn place_order<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ManageOrders<'info>>,
+    side: OrderSide,
+    price: u64,
+    quantity: u64
+) -> Result<()> {
+    require_trading_open(&mut ctx.accounts.market, &ctx.accounts.config)?;
+    require!(quantity >= ctx.accounts.order_book.min_order_size.max(1), ErrorCode::OrderTooSmall);
+    require!(
+        price > 0 && price < ctx.accounts.market.collateral_per_token,
+        ErrorCode::InvalidOrderPrice
     );
-    token::mint_to(yes_mint_ctx, 500_000)?;
-    msg!("Minted 500 000 yes tokens!");
 
-    let bump_no=&[ ctx.bumps.no_mint];
-    let seeds_no = &["no_mint".as_bytes(), market_key.as_ref(), bump_no];
-    let signer_no:&[&[&[u8]]] = &[&seeds_no[..]];
+    let open_orders = &mut ctx.accounts.open_orders;
+    if open_orders.owner == Pubkey::default() {
+        open_orders.market = ctx.accounts.market.key();
+        open_orders.owner = ctx.accounts.user.key();
+        open_orders.bump = ctx.bumps.open_orders;
+    }
 
-    let no_mint_ctx = CpiContext::new_with_signer(
-        token_program.to_account_info(),
-        MintTo {
-            mint: ctx.accounts.no_mint.to_account_info(),
-            to: ctx.accounts.treasury_no_token_account.to_account_info(),
-            authority: ctx.accounts.no_mint.to_account_info(),
-        },
-        signer_no
-    );
-    token::mint_to(no_mint_ctx, 500_000)?;
-    msg!("Minted 500 000 no tokens!");
+    if let Some(evicted) = ctx.accounts.order_book.evict_worst(side, price) {
+        let (collateral, yes) = match side {
+            OrderSide::Bid => (evicted.price.checked_mul(evicted.quantity).ok_or(ErrorCode::Overflow)?, 0),
+            OrderSide::Ask => (0, evicted.quantity),
+        };
+        if evicted.owner == ctx.accounts.user.key() {
+            let open_orders = &mut ctx.accounts.open_orders;
+            open_orders.collateral_free = open_orders.collateral_free
+                .checked_add(collateral)
+                .ok_or(ErrorCode::Overflow)?;
+            open_orders.yes_free = open_orders.yes_free.checked_add(yes).ok_or(ErrorCode::Overflow)?;
+        } else {
+            let owner_info = find_open_orders(ctx.remaining_accounts, &ctx.accounts.market.key(), &evicted.owner)?
+                .ok_or(ErrorCode::InvalidOpenOrders)?;
+            credit_open_orders(owner_info, collateral, yes)?;
+        }
+        msg!("⚠️ Evicted {:?} #{}: {} YES at {}", side, evicted.order_id, evicted.quantity, evicted.price);
+    }
 
+    match side {
+        OrderSide::Bid => {
+            let escrow = price.checked_mul(quantity).ok_or(ErrorCode::Overflow)?;
+            deposit_collateral(
+                &ctx.accounts.collateral_token_program,
+                &ctx.accounts.collateral_mint,
+                ctx.accounts.user_collateral_account.to_account_info(),
+                &mut ctx.accounts.book_collateral_vault,
+                ctx.accounts.user.to_account_info(),
+                escrow
+            )?;
+        }
+        OrderSide::Ask => {
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                &ctx.accounts.yes_mint,
+                ctx.accounts.user_yes_token_account.to_account_info(),
+                ctx.accounts.book_yes_vault.to_account_info(),
+                ctx.accounts.user.to_account_info(),
+                &[],
+                quantity
+            )?;
+        }
+    }
 
+    let order_book = &mut ctx.accounts.order_book;
+    let order_id = order_book.next_order_id;
+    order_book.next_order_id = order_id.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    order_book.insert(side, Order {
+        order_id,
+        owner: ctx.accounts.user.key(),
+        price,
+        quantity,
+    })?;
+    msg!("✅ Placed {:?} #{}: {} YES at {}", side, order_id, quantity, price);
     Ok(())
 }
+
+/// Removes the user's order and refunds its unfilled escrow. Available after
+/// expiry so resting orders can always be withdrawn.
+pub fn cancel_order(ctx: Context<ManageOrders>, order_id: u64) -> Result<()> {
+    let (side, order) = ctx.accounts.order_book.remove(order_id).ok_or(ErrorCode::OrderNotFound)?;
+    require_keys_eq!(order.owner, ctx.accounts.user.key(), ErrorCode::Unauthorized);
+
+    let market = &ctx.accounts.market;
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+    match side {
+        OrderSide::Bid => {
+            transfer_tokens(
+                &ctx.accounts.collateral_token_program,
+                &ctx.accounts.collateral_mint,
+                ctx.accounts.book_collateral_vault.to_account_info(),
+                ctx.accounts.user_collateral_account.to_account_info(),
+                market.to_account_info(),
+                signer,
+                order.price.checked_mul(order.quantity).ok_or(ErrorCode::Overflow)?
+            )?;
+        }
+        OrderSide::Ask => {
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                &ctx.accounts.yes_mint,
+                ctx.accounts.book_yes_vault.to_account_info(),
+                ctx.accounts.user_yes_token_account.to_account_info(),
+                market.to_account_info(),
+                signer,
+                order.quantity
+            )?;
+        }
+    }
+    msg!("✅ Cancelled {:?} #{} with {} YES unfilled", side, order_id, order.quantity);
+    Ok(())
+}
+
+/// Crosses the best bid and ask up to `limit` times, queueing each fill.
+pub fn match_orders(ctx: Context<MatchOrders>, limit: u8) -> Result<()> {
+    require_trading_open(&mut ctx.accounts.market, &ctx.accounts.config)?;
+    let order_book = &mut ctx.accounts.order_book;
+    let event_queue = &mut ctx.accounts.event_queue;
+    require!(event_queue.events.len() < MAX_FILL_EVENTS, ErrorCode::EventQueueFull);
+
+    let mut fills = 0;
+    while fills < limit && event_queue.events.len() < MAX_FILL_EVENTS {
+        let (Some(bid), Some(ask)) = (order_book.bids.first().copied(), order_book.asks.first().copied()) else {
+            break;
+        };
+        if bid.price < ask.price {
+            break;
+        }
+        let quantity = bid.quantity.min(ask.quantity);
+        // The order that rested first sets the trade price
+        let price = if bid.order_id < ask.order_id { bid.price } else { ask.price };
+        event_queue.events.push(FillEvent {
+            bid_owner: bid.owner,
+            ask_owner: ask.owner,
+            price,
+            bid_price: bid.price,
+            quantity,
+        });
+        order_book.fill_best(OrderSide::Bid, quantity);
+        order_book.fill_best(OrderSide::Ask, quantity);
+        fills += 1;
+    }
+    require!(fills > 0, ErrorCode::NothingToMatch);
+    msg!("✅ Matched {} fills", fills);
+    Ok(())
+}
+
+/// Credits up to `limit` queued fills to the owners' `OpenOrders`, passed
+/// as remaining accounts. Stops at the first fill whose accounts are missing.
+pub fn consume_events<'info>(ctx: Context<'_, '_, 'info, 'info, ConsumeEvents<'info>>, limit: u8) -> Result<()> {
+    let market_key = ctx.accounts.market.key();
+    let event_queue = &mut ctx.accounts.event_queue;
+
+    let mut consumed = 0;
+    while consumed < (limit as usize) && consumed < event_queue.events.len() {
+        let event = event_queue.events[consumed];
+        let bid_refund = event.bid_price
+            .checked_sub(event.price)
+            .and_then(|improvement| improvement.checked_mul(event.quantity))
+            .ok_or(ErrorCode::Overflow)?;
+        let ask_proceeds = event.price.checked_mul(event.quantity).ok_or(ErrorCode::Overflow)?;
+
+        let (Some(bid_info), Some(ask_info)) = (
+            find_open_orders(ctx.remaining_accounts, &market_key, &event.bid_owner)?,
+            find_open_orders(ctx.remaining_accounts, &market_key, &event.ask_owner)?,
+        ) else {
+            break;
+        };
+        credit_open_orders(bid_info, bid_refund, event.quantity)?;
+        credit_open_orders(ask_info, ask_proceeds, 0)?;
+        consumed += 1;
+    }
+    require!(consumed > 0, ErrorCode::NothingToMatch);
+    event_queue.events.drain(..consumed);
+    msg!("✅ Consumed {} fill events", consumed);
+    Ok(())
+}
+
+/// Withdraws the user's credited order book balances.
+pub fn settle_funds(ctx: Context<ManageOrders>) -> Result<()> {
+    let collateral_free = ctx.accounts.open_orders.collateral_free;
+    let yes_free = ctx.accounts.open_orders.yes_free;
+    require!(collateral_free > 0 || yes_free > 0, ErrorCode::InsufficientFunds);
+
+    let market = &ctx.accounts.market;
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+    if collateral_free > 0 {
+        transfer_tokens(
+            &ctx.accounts.collateral_token_program,
+            &ctx.accounts.collateral_mint,
+            ctx.accounts.book_collateral_vault.to_account_info(),
+            ctx.accounts.user_collateral_account.to_account_info(),
+            market.to_account_info(),
+            signer,
+            collateral_free
+        )?;
+    }
+    if yes_free > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.yes_mint,
+            ctx.accounts.book_yes_vault.to_account_info(),
+            ctx.accounts.user_yes_token_account.to_account_info(),
+            market.to_account_info(),
+            signer,
+            yes_free
+        )?;
+    }
+
+    let open_orders = &mut ctx.accounts.open_orders;
+    open_orders.collateral_free = 0;
+    open_orders.yes_free = 0;
+    msg!("✅ Settled {} collateral and {} YES", collateral_free, yes_free);
+    Ok(())
+}
+
+/// Spends at most `collateral_in`, fees included, on YES: mints complete sets
+/// and sells the NO leg to the pool, returning at least `min_yes_out` YES.
+pub fn buy_yes(ctx: Context<PoolTrade>, collateral_in: u64, min_yes_out: u64) -> Result<()> {
+    buy_outcome(ctx, Side::Yes, collateral_in, min_yes_out)
+}
+
+/// Mirror of `buy_yes` for NO.
+pub fn buy_no(ctx: Context<PoolTrade>, collateral_in: u64, min_no_out: u64) -> Result<()> {
+    buy_outcome(ctx, Side::No, collateral_in, min_no_out)
+}
+
+/// Exits `yes_in` YES: sells part of it to the pool for NO and burns the
+/// resulting pairs for at least `min_collateral_out` collateral.
+pub fn sell_yes(ctx: Context<PoolTrade>, yes_in: u64, min_collateral_out: u64) -> Result<()> {
+    sell_outcome(ctx, Side::Yes, yes_in, min_collateral_out)
+}
+
+/// Mirror of `sell_yes` for NO.
+pub fn sell_no(ctx: Context<PoolTrade>, no_in: u64, min_collateral_out: u64) -> Result<()> {
+    sell_outcome(ctx, Side::No, no_in, min_collateral_out)
+}
+
+/// Closes out a settled market once every outcome token has been redeemed.
+/// Any collateral left in the vault is rounding dust from void redemptions or
+/// backed pool reserves burned by `redeem_pool_reserves`, and goes to the
+/// creator before the vault is closed. Bucket markets pass
+/// their created bucket mints, in order, as remaining accounts instead of
+/// the YES/NO mints.
+pub fn close_market<'info>(ctx: Context<'_, '_, 'info, 'info, CloseMarket<'info>>) -> Result<()> {
+    let market = &ctx.accounts.market;
+    market.require_status(
+        &[MarketStatus::Resolved, MarketStatus::Voided],
+        ErrorCode::MarketNotResolved
+    )?;
+    if market.bucket_count > 0 {
+        require!(
+            ctx.remaining_accounts.len() == (market.bucket_mints_created as usize),
+            ErrorCode::InvalidBucketAccounts
+        );
+        let market_key = market.key();
+        for (index, mint_info) in ctx.remaining_accounts.iter().enumerate() {
+            let (bucket_mint, _) = Pubkey::find_program_address(
+                &[b"bucket_mint", market_key.as_ref(), &[index as u8]],
+                &crate::ID
+            );
+            require!(mint_info.key() == bucket_mint, ErrorCode::InvalidBucketAccounts);
+            let mint = InterfaceAccount::<Mint>::try_from(mint_info)?;
+            require!(mint.supply == 0, ErrorCode::OutstandingTokens);
+        }
+    } else {
+        let (Some(yes_mint), Some(no_mint)) = (&ctx.accounts.yes_mint, &ctx.accounts.no_mint) else {
+            return Err(ErrorCode::MintsNotReady.into());
+        };
+        require!(yes_mint.supply == 0 && no_mint.supply == 0, ErrorCode::OutstandingTokens);
+    }
+
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+
+    let dust = ctx.accounts.collateral_vault.amount;
+    if dust > 0 {
+        let sweep_ctx = CpiContext::new_with_signer(
+            ctx.accounts.collateral_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.collateral_vault.to_account_info(),
+                mint: ctx.accounts.collateral_mint.to_account_info(),
+                to: ctx.accounts.authority_collateral_account.to_account_info(),
+                authority: market.to_account_info(),
+            },
+            signer
+        );
+        token_interface::transfer_checked(sweep_ctx, dust, ctx.accounts.collateral_mint.decimals)?;
+    }
+
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.collateral_token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.collateral_vault.to_account_info(),
+            destination: ctx.accounts.authority.to_account_info(),
+            authority: market.to_account_info(),
+        },
+        signer
+    );
+    token_interface::close_account(close_ctx)?;
+
+    ctx.accounts.market.status = MarketStatus::Closed;
+    msg!("✅ Market closed, swept {} leftover collateral to the creator", dust);
+    Ok(())
+}
+
+/// Locks `amount * market.collateral_per_token` collateral in the market vault
+/// and mints `amount` YES and `amount` NO to the user, so outstanding supply of
+/// each outcome always equals the number of locked collateral units.
+pub fn mint_complete_set(ctx: Context<MintCompleteSet>, amount: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    ctx.accounts.config.require_not_paused(market, PAUSE_MINT)?;
+    market.refresh_status(Clock::get()?.unix_timestamp);
+    match market.status {
+        MarketStatus::Expired => {
+            return Err(ErrorCode::MarketAlreadyExpired.into());
+        }
+        _ => market.require_status(&[MarketStatus::Open], ErrorCode::MarketNotOpen)?,
+    }
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let collateral_to_lock = amount.checked_mul(market.collateral_per_token).ok_or(ErrorCode::Overflow)?;
+    // The creator fee is held in the market vault alongside the locked
+    // collateral; the protocol fee is paid straight to the treasury
+    let creator_fee = bps_of(collateral_to_lock, market.creator_fee_bps)?;
+    let protocol_fee = bps_of(collateral_to_lock, ctx.accounts.config.deposit_fee_bps)?;
+
+    deposit_collateral(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.collateral_mint,
+        ctx.accounts.user_collateral_account.to_account_info(),
+        &mut ctx.accounts.collateral_vault,
+        ctx.accounts.user.to_account_info(),
+        collateral_to_lock.checked_add(creator_fee).ok_or(ErrorCode::Overflow)?
+    )?;
+    if protocol_fee > 0 {
+        deposit_collateral(
+            &ctx.accounts.collateral_token_program,
+            &ctx.accounts.collateral_mint,
+            ctx.accounts.user_collateral_account.to_account_info(),
+            &mut ctx.accounts.treasury_vault,
+            ctx.accounts.user.to_account_info(),
+            protocol_fee
+        )?;
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.total_deposit_fees = treasury.total_deposit_fees
+            .checked_add(protocol_fee)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+    let market = &mut ctx.accounts.market;
+    market.creator_fees_accrued = market.creator_fees_accrued
+        .checked_add(creator_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let signer_seeds = ctx.accounts.market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+
+    // ✅ Mint YES tokens
+    let yes_mint_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        MintTo {
+            mint: ctx.accounts.yes_mint.to_account_info(),
+            to: ctx.accounts.user_yes_token_account.to_account_info(),
+            authority: ctx.accounts.market.to_account_info(),
+        },
+        signer
+    );
+    token_interface::mint_to(yes_mint_ctx, amount)?;
+
+    // ✅ Mint NO tokens
+    let no_mint_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        MintTo {
+            mint: ctx.accounts.no_mint.to_account_info(),
+            to: ctx.accounts.user_no_token_account.to_account_info(),
+            authority: ctx.accounts.market.to_account_info(),
+        },
+        signer
+    );
+    token_interface::mint_to(no_mint_ctx, amount)?;
+
+    msg!(
+        "User locked {} collateral (protocol fee {}, creator fee {}) and received {} YES and NO tokens each.",
+        collateral_to_lock,
+        protocol_fee,
+        creator_fee,
+        amount
+    );
+
+    Ok(())
+}
+
+/// Early exit before resolution: burns `amount` YES and `amount` NO from the
+/// user and returns the `amount * collateral_per_token` locked for them.
+pub fn burn_complete_set(ctx: Context<BurnCompleteSet>, amount: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    ctx.accounts.config.require_not_paused(market, PAUSE_MINT)?;
+    market.refresh_status(Clock::get()?.unix_timestamp);
+    market.require_status(
+        &[MarketStatus::Open, MarketStatus::Expired],
+        ErrorCode::MarketAlreadyResolved
+    )?;
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        ctx.accounts.user_yes_token_account.amount >= amount &&
+            ctx.accounts.user_no_token_account.amount >= amount,
+        ErrorCode::InsufficientTokens
+    );
+
+    let collateral_to_return = amount
+        .checked_mul(ctx.accounts.market.collateral_per_token)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let user = &ctx.accounts.user;
+    let token_program = &ctx.accounts.token_program;
+
+    let burn_yes_ctx = CpiContext::new(token_program.to_account_info(), Burn {
+        mint: ctx.accounts.yes_mint.to_account_info(),
+        from: ctx.accounts.user_yes_token_account.to_account_info(),
+        authority: user.to_account_info(),
+    });
+    token_interface::burn(burn_yes_ctx, amount)?;
+
+    let burn_no_ctx = CpiContext::new(token_program.to_account_info(), Burn {
+        mint: ctx.accounts.no_mint.to_account_info(),
+        from: ctx.accounts.user_no_token_account.to_account_info(),
+        authority: user.to_account_info(),
+    });
+    token_interface::burn(burn_no_ctx, amount)?;
+
+    // ✅ Return collateral from the market vault to the user
+    let signer_seeds = ctx.accounts.market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+    let withdraw_ctx = CpiContext::new_with_signer(
+        ctx.accounts.collateral_token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.collateral_vault.to_account_info(),
+            mint: ctx.accounts.collateral_mint.to_account_info(),
+            to: ctx.accounts.user_collateral_account.to_account_info(),
+            authority: ctx.accounts.market.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(withdraw_ctx, collateral_to_return, ctx.accounts.collateral_mint.decimals)?;
+
+    msg!(
+        "✅ Burned {} complete sets and returned {} collateral to user",
+        amount,
+        collateral_to_return
+    );
+
+    Ok(())
+}
+
+pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    // let market_authority = &mut ctx.accounts.market_authority;
+    let user = &ctx.accounts.user;
+    let token_program = &ctx.accounts.token_program;
+
+    ctx.accounts.config.require_not_paused(market, PAUSE_REDEEM)?;
+
+    // ✅ Ensure the market has been resolved
+    market.require_status(
+        &[MarketStatus::Resolved, MarketStatus::Voided],
+        ErrorCode::MarketNotResolved
+    )?;
+
+    // ✅ Determine which tokens are burned and what they pay out
+    // Holders only need to pass the accounts they actually hold
+    let yes_balance = ctx.accounts.user_yes_token_account.as_ref().map_or(0, |account| account.amount);
+    let no_balance = ctx.accounts.user_no_token_account.as_ref().map_or(0, |account| account.amount);
+    let (yes_to_burn, no_to_burn, total_collateral) = match market.outcome {
+        // Losing tokens are burned alongside the winners so no account is stranded
+        Some(Outcome::Yes) => {
+            msg!("✅ Market outcome is YES. Burning all YES and NO tokens.");
+            let payout = yes_balance.checked_mul(market.collateral_per_token);
+            (yes_balance, no_balance, payout)
+        }
+        Some(Outcome::No) => {
+            msg!("✅ Market outcome is NO. Burning all YES and NO tokens.");
+            let payout = no_balance.checked_mul(market.collateral_per_token);
+            (yes_balance, no_balance, payout)
+        }
+        Some(Outcome::Void) => {
+            // Void: every YES and every NO is worth half a collateral unit
+            msg!("✅ Market is void. Burning all YES and NO tokens for half a unit each.");
+            let payout = (yes_balance as u128)
+                .checked_add(no_balance as u128)
+                .and_then(|tokens| tokens.checked_mul(market.collateral_per_token as u128))
+                .and_then(|collateral| u64::try_from(collateral / 2).ok());
+            (yes_balance, no_balance, payout)
+        }
+        None => {
+            return Err(ErrorCode::MarketNotResolved.into());
+        }
+    };
+    let total_collateral = total_collateral.ok_or(ErrorCode::Overflow)?;
+    require!(yes_to_burn > 0 || no_to_burn > 0, ErrorCode::InsufficientTokens);
+
+    // Void refunds are not winnings and carry no protocol fee
+    let protocol_fee = match market.outcome {
+        Some(Outcome::Void) => 0,
+        _ => bps_of(total_collateral, ctx.accounts.config.redeem_fee_bps)?,
+    };
+    let user_payout = total_collateral.checked_sub(protocol_fee).ok_or(ErrorCode::Overflow)?;
+
+    // ✅ Burn the redeemed tokens and close their accounts to reclaim rent
+    if let Some(user_yes_token_account) = ctx.accounts.user_yes_token_account.as_ref().filter(|_| yes_to_burn > 0) {
+        burn_and_close(token_program, &ctx.accounts.yes_mint, user_yes_token_account, user, yes_to_burn)?;
+    }
+    if let Some(user_no_token_account) = ctx.accounts.user_no_token_account.as_ref().filter(|_| no_to_burn > 0) {
+        burn_and_close(token_program, &ctx.accounts.no_mint, user_no_token_account, user, no_to_burn)?;
+    }
+
+    msg!(
+        "✅ Burned {} YES and {} NO tokens for user. Transferring {} collateral...",
+        yes_to_burn,
+        no_to_burn,
+        total_collateral
+    );
+
+    // ✅ Transfer collateral from the market vault to the user
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+    let payout_ctx = CpiContext::new_with_signer(
+        ctx.accounts.collateral_token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.collateral_vault.to_account_info(),
+            mint: ctx.accounts.collateral_mint.to_account_info(),
+            to: ctx.accounts.user_collateral_account.to_account_info(),
+            authority: market.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(payout_ctx, user_payout, ctx.accounts.collateral_mint.decimals)?;
+
+    if protocol_fee > 0 {
+        let fee_ctx = CpiContext::new_with_signer(
+            ctx.accounts.collateral_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.collateral_vault.to_account_info(),
+                mint: ctx.accounts.collateral_mint.to_account_info(),
+                to: ctx.accounts.treasury_vault.to_account_info(),
+                authority: market.to_account_info(),
+            },
+            signer
+        );
+        token_interface::transfer_checked(fee_ctx, protocol_fee, ctx.accounts.collateral_mint.decimals)?;
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.total_redeem_fees = treasury.total_redeem_fees
+            .checked_add(protocol_fee)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+    msg!(
+        "✅ Successfully redeemed and transferred {} collateral to user (fee {})",
+        user_payout,
+        protocol_fee
+    );
+
+    Ok(())
+}
+
+/// Creates the outcome mint for bucket `index` of a bucket market. Buckets
+/// are created in order; the market is `MintsReady` once the last exists.
+pub fn create_bucket_mint(ctx: Context<CreateBucketMint>, index: u8) -> Result<()> {
+    let market = &ctx.accounts.market;
+    market.require_status(&[MarketStatus::Created], ErrorCode::MintsAlreadyCreated)?;
+    require!(market.bucket_count > 0, ErrorCode::UnsupportedMarketType);
+    require!(index == market.bucket_mints_created, ErrorCode::InvalidBucketIndex);
+
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer: &[&[&[u8]]] = &[&market_seeds[..]];
+
+    let symbol = format!("B{}", index);
+    let name = format!(
+        "{}_IN_{}_EXP:{}_{}",
+        ctx.accounts.asset_config.symbol,
+        market.bucket_label(index),
+        market.expiry,
+        symbol
+    );
+    initialize_outcome_metadata(
+        &ctx.accounts.token_program,
+        &ctx.accounts.bucket_mint,
+        market,
+        &ctx.accounts.authority,
+        &ctx.accounts.system_program,
+        signer,
+        name,
+        symbol,
+        "*".to_string()
+    )?;
+
+    let market = &mut ctx.accounts.market;
+    market.bucket_mints_created += 1;
+    if market.bucket_mints_created == market.bucket_count {
+        market.status = MarketStatus::MintsReady;
+    }
+    msg!("✅ Bucket {} mint created ({}/{})", index, market.bucket_mints_created, market.bucket_count);
+    Ok(())
+}
+
+/// Bucket-market counterpart of `mint_complete_set`: locks
+/// `amount * market.collateral_per_token` collateral and mints `amount` of
+/// every bucket's token. Remaining accounts are `[bucket_mint, user token
+/// account]` pairs for buckets `0..bucket_count`, in order.
+pub fn mint_bucket_set<'info>(ctx: Context<'_, '_, 'info, 'info, MintBucketSet<'info>>, amount: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    ctx.accounts.config.require_not_paused(market, PAUSE_MINT)?;
+    market.refresh_status(Clock::get()?.unix_timestamp);
+    match market.status {
+        MarketStatus::Expired => {
+            return Err(ErrorCode::MarketAlreadyExpired.into());
+        }
+        _ => market.require_status(&[MarketStatus::Open], ErrorCode::MarketNotOpen)?,
+    }
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(market.bucket_count > 0, ErrorCode::UnsupportedMarketType);
+    require!(
+        ctx.remaining_accounts.len() == 2 * (market.bucket_count as usize),
+        ErrorCode::InvalidBucketAccounts
+    );
+
+    let collateral_to_lock = amount.checked_mul(market.collateral_per_token).ok_or(ErrorCode::Overflow)?;
+    let creator_fee = bps_of(collateral_to_lock, market.creator_fee_bps)?;
+    let protocol_fee = bps_of(collateral_to_lock, ctx.accounts.config.deposit_fee_bps)?;
+
+    deposit_collateral(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.collateral_mint,
+        ctx.accounts.user_collateral_account.to_account_info(),
+        &mut ctx.accounts.collateral_vault,
+        ctx.accounts.user.to_account_info(),
+        collateral_to_lock.checked_add(creator_fee).ok_or(ErrorCode::Overflow)?
+    )?;
+    if protocol_fee > 0 {
+        deposit_collateral(
+            &ctx.accounts.collateral_token_program,
+            &ctx.accounts.collateral_mint,
+            ctx.accounts.user_collateral_account.to_account_info(),
+            &mut ctx.accounts.treasury_vault,
+            ctx.accounts.user.to_account_info(),
+            protocol_fee
+        )?;
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.total_deposit_fees = treasury.total_deposit_fees
+            .checked_add(protocol_fee)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+    let market = &mut ctx.accounts.market;
+    market.creator_fees_accrued = market.creator_fees_accrued
+        .checked_add(creator_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let market = &ctx.accounts.market;
+    let market_key = market.key();
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+
+    for (index, pair) in ctx.remaining_accounts.chunks(2).enumerate() {
+        let (mint_info, user_account_info) = (&pair[0], &pair[1]);
+        let (bucket_mint, _) = Pubkey::find_program_address(
+            &[b"bucket_mint", market_key.as_ref(), &[index as u8]],
+            &crate::ID
+        );
+        let user_account = InterfaceAccount::<TokenAccount>::try_from(user_account_info)?;
+        require!(
+            mint_info.key() == bucket_mint &&
+                user_account.mint == bucket_mint &&
+                user_account.owner == ctx.accounts.user.key(),
+            ErrorCode::InvalidBucketAccounts
+        );
+
+        let mint_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: mint_info.clone(),
+                to: user_account_info.clone(),
+                authority: market.to_account_info(),
+            },
+            signer
+        );
+        token_interface::mint_to(mint_ctx, amount)?;
+    }
+
+    msg!(
+        "User locked {} collateral (protocol fee {}, creator fee {}) and received {} of each of {} bucket tokens.",
+        collateral_to_lock,
+        protocol_fee,
+        creator_fee,
+        amount,
+        market.bucket_count
+    );
+    Ok(())
+}
+
+/// Burns the user's whole balance of bucket `index` and closes the account.
+/// Only the winning bucket pays; on a void market every bucket pays
+/// `1 / bucket_count` of a collateral unit per token.
+pub fn redeem_bucket(ctx: Context<RedeemBucket>, index: u8) -> Result<()> {
+    let market = &ctx.accounts.market;
+    ctx.accounts.config.require_not_paused(market, PAUSE_REDEEM)?;
+    market.require_status(
+        &[MarketStatus::Resolved, MarketStatus::Voided],
+        ErrorCode::MarketNotResolved
+    )?;
+    require!(market.bucket_count > 0, ErrorCode::UnsupportedMarketType);
+    require!(index < market.bucket_count, ErrorCode::InvalidBucketIndex);
+
+    let balance = ctx.accounts.user_bucket_token_account.amount;
+    require!(balance > 0, ErrorCode::InsufficientTokens);
+    let total_collateral = match market.outcome {
+        Some(Outcome::Yes) if index == market.winning_bucket => balance.checked_mul(market.collateral_per_token),
+        Some(Outcome::Yes) => Some(0),
+        Some(Outcome::Void) =>
+            (balance as u128)
+                .checked_mul(market.collateral_per_token as u128)
+                .and_then(|collateral| u64::try_from(collateral / (market.bucket_count as u128)).ok()),
+        Some(Outcome::No) | None => {
+            return Err(ErrorCode::MarketNotResolved.into());
+        }
+    };
+    let total_collateral = total_collateral.ok_or(ErrorCode::Overflow)?;
+
+    // Void refunds are not winnings and carry no protocol fee
+    let protocol_fee = match market.outcome {
+        Some(Outcome::Void) => 0,
+        _ => bps_of(total_collateral, ctx.accounts.config.redeem_fee_bps)?,
+    };
+    let user_payout = total_collateral.checked_sub(protocol_fee).ok_or(ErrorCode::Overflow)?;
+
+    // Losing buckets are burned too so no account is stranded
+    burn_and_close(
+        &ctx.accounts.token_program,
+        &ctx.accounts.bucket_mint,
+        &ctx.accounts.user_bucket_token_account,
+        &ctx.accounts.user,
+        balance
+    )?;
+    msg!("✅ Burned {} bucket {} tokens for user", balance, index);
+    if total_collateral == 0 {
+        return Ok(());
+    }
+
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+    transfer_tokens(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.collateral_mint,
+        ctx.accounts.collateral_vault.to_account_info(),
+        ctx.accounts.user_collateral_account.to_account_info(),
+        market.to_account_info(),
+        signer,
+        user_payout
+    )?;
+    if protocol_fee > 0 {
+        transfer_tokens(
+            &ctx.accounts.collateral_token_program,
+            &ctx.accounts.collateral_mint,
+            ctx.accounts.collateral_vault.to_account_info(),
+            ctx.accounts.treasury_vault.to_account_info(),
+            market.to_account_info(),
+            signer,
+            protocol_fee
+        )?;
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.total_redeem_fees = treasury.total_redeem_fees
+            .checked_add(protocol_fee)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+    msg!(
+        "✅ Successfully redeemed and transferred {} collateral to user (fee {})",
+        user_payout,
+        protocol_fee
+    );
+    Ok(())
+}
+
+/// Mints as many complete sets as `collateral_in` covers once the same fees
+/// as `mint_complete_set` are taken out of it, keeps the `side` leg for the
+/// user and swaps the other leg into the pool straight from the mint. Any
+/// Token-2022 transfer fee on the collateral mint is charged on top.
+fn buy_outcome(ctx: Context<PoolTrade>, side: Side, collateral_in: u64, min_out: u64) -> Result<()> {
+    ctx.accounts.config.require_not_paused(&ctx.accounts.market, PAUSE_MINT)?;
+    require_trading_open(&mut ctx.accounts.market, &ctx.accounts.config)?;
+
+    let market = &ctx.accounts.market;
+    // Fees are charged on the locked collateral, so `collateral_in` has to
+    // cover `sets * collateral_per_token * (1 + fee_bps / 10_000)`
+    let fee_bps = BPS_DENOMINATOR
+        + (market.creator_fee_bps as u64)
+        + (ctx.accounts.config.deposit_fee_bps as u64);
+    let cost_per_set = market.collateral_per_token.checked_mul(fee_bps).ok_or(ErrorCode::Overflow)?;
+    let sets = mul_div(collateral_in, BPS_DENOMINATOR, cost_per_set)?;
+    require!(sets > 0, ErrorCode::InvalidAmount);
+    let collateral_to_lock = sets.checked_mul(market.collateral_per_token).ok_or(ErrorCode::Overflow)?;
+    let creator_fee = bps_of(collateral_to_lock, market.creator_fee_bps)?;
+    let protocol_fee = bps_of(collateral_to_lock, ctx.accounts.config.deposit_fee_bps)?;
+
+    deposit_collateral(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.collateral_mint,
+        ctx.accounts.user_collateral_account.to_account_info(),
+        &mut ctx.accounts.collateral_vault,
+        ctx.accounts.user.to_account_info(),
+        collateral_to_lock.checked_add(creator_fee).ok_or(ErrorCode::Overflow)?
+    )?;
+    if protocol_fee > 0 {
+        deposit_collateral(
+            &ctx.accounts.collateral_token_program,
+            &ctx.accounts.collateral_mint,
+            ctx.accounts.user_collateral_account.to_account_info(),
+            &mut ctx.accounts.treasury_vault,
+            ctx.accounts.user.to_account_info(),
+            protocol_fee
+        )?;
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.total_deposit_fees = treasury.total_deposit_fees
+            .checked_add(protocol_fee)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+
+    let accounts = &ctx.accounts;
+    let (kept_mint, user_kept, pool_kept, sold_mint, pool_sold) = match side {
+        Side::Yes =>
+            (
+                &accounts.yes_mint,
+                &accounts.user_yes_token_account,
+                &accounts.pool_yes_vault,
+                &accounts.no_mint,
+                &accounts.pool_no_vault,
+            ),
+        Side::No =>
+            (
+                &accounts.no_mint,
+                &accounts.user_no_token_account,
+                &accounts.pool_no_vault,
+                &accounts.yes_mint,
+                &accounts.pool_yes_vault,
+            ),
+    };
+    let side_sold = match side {
+        Side::Yes => Side::No,
+        Side::No => Side::Yes,
+    };
+    let (reserve_in, reserve_out) = accounts.pool.reserves(side_sold);
+    let swapped_out = cp_amount_out(sets, reserve_in, reserve_out, accounts.pool.fee_bps)?;
+    let total_out = sets.checked_add(swapped_out).ok_or(ErrorCode::Overflow)?;
+    require!(total_out >= min_out, ErrorCode::SlippageExceeded);
+
+    let market = &accounts.market;
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer = &[&market_seeds[..]];
+    for (mint, to) in [(kept_mint, user_kept.to_account_info()), (sold_mint, pool_sold.to_account_info())] {
+        let mint_ctx = CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            MintTo {
+                mint: mint.to_account_info(),
+                to,
+                authority: market.to_account_info(),
+            },
+            signer
+        );
+        token_interface::mint_to(mint_ctx, sets)?;
+    }
+
+    if swapped_out > 0 {
+        let market_key = market.key();
+        let pool_seeds = &[b"pool".as_ref(), market_key.as_ref(), &[accounts.pool.bump]];
+        transfer_tokens(
+            &accounts.token_program,
+            kept_mint,
+            pool_kept.to_account_info(),
+            user_kept.to_account_info(),
+            accounts.pool.to_account_info(),
+            &[&pool_seeds[..]],
+            swapped_out
+        )?;
+    }
+
+    ctx.accounts.pool.apply_swap(side_sold, sets, swapped_out)?;
+    let market = &mut ctx.accounts.market;
+    market.creator_fees_accrued = market.creator_fees_accrued
+        .checked_add(creator_fee)
+        .ok_or(ErrorCode::Overflow)?;
+    emit!(SwapExecuted {
+        market: market.key(),
+        user: ctx.accounts.user.key(),
+        side_in: side_sold,
+        amount_in: sets,
+        amount_out: swapped_out,
+    });
+    msg!("✅ Bought {} {:?} for {} collateral", total_out, side, collateral_to_lock);
+    Ok(())
+}
+
+/// Swaps just enough of `amount_in` into the pool that the user holds equal
+/// YES and NO, then burns those pairs for collateral. Any single leftover
+/// token from rounding stays with the user.
+fn sell_outcome(ctx: Context<PoolTrade>, side: Side, amount_in: u64, min_collateral_out: u64) -> Result<()> {
+    ctx.accounts.config.require_not_paused(&ctx.accounts.market, PAUSE_MINT)?;
+    require_trading_open(&mut ctx.accounts.market, &ctx.accounts.config)?;
+    require!(amount_in > 0, ErrorCode::InvalidAmount);
+
+    let accounts = &ctx.accounts;
+    let (reserve_in, reserve_out) = accounts.pool.reserves(side);
+    let fee_bps = accounts.pool.fee_bps;
+
+    // Largest swap whose output does not exceed what is left unswapped
+    let (mut low, mut high) = (0u64, amount_in);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if cp_amount_out(mid, reserve_in, reserve_out, fee_bps)? <= amount_in - mid {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    let mut swap_in = low;
+    let mut swapped_out = cp_amount_out(swap_in, reserve_in, reserve_out, fee_bps)?;
+    if swap_in < amount_in && amount_in - swap_in - 1 > swapped_out {
+        swap_in += 1;
+        swapped_out = cp_amount_out(swap_in, reserve_in, reserve_out, fee_bps)?;
+    }
+    let sets = swapped_out.min(amount_in - swap_in);
+    require!(sets > 0, ErrorCode::InsufficientLiquidity);
+
+    let collateral_out = sets.checked_mul(accounts.market.collateral_per_token).ok_or(ErrorCode::Overflow)?;
+    require!(collateral_out >= min_collateral_out, ErrorCode::SlippageExceeded);
+
+    let (sold_mint, user_sold, pool_sold, other_mint, user_other, pool_other) = match side {
+        Side::Yes =>
+            (
+                &accounts.yes_mint,
+                &accounts.user_yes_token_account,
+                &accounts.pool_yes_vault,
+                &accounts.no_mint,
+                &accounts.user_no_token_account,
+                &accounts.pool_no_vault,
+            ),
+        Side::No =>
+            (
+                &accounts.no_mint,
+                &accounts.user_no_token_account,
+                &accounts.pool_no_vault,
+                &accounts.yes_mint,
+                &accounts.user_yes_token_account,
+                &accounts.pool_yes_vault,
+            ),
+    };
+
+    let user = accounts.user.to_account_info();
+    transfer_tokens(
+        &accounts.token_program,
+        sold_mint,
+        user_sold.to_account_info(),
+        pool_sold.to_account_info(),
+        user.clone(),
+        &[],
+        swap_in
+    )?;
+    let market_key = accounts.market.key();
+    let pool_seeds = &[b"pool".as_ref(), market_key.as_ref(), &[accounts.pool.bump]];
+    transfer_tokens(
+        &accounts.token_program,
+        other_mint,
+        pool_other.to_account_info(),
+        user_other.to_account_info(),
+        accounts.pool.to_account_info(),
+        &[&pool_seeds[..]],
+        swapped_out
+    )?;
+
+    for (mint, from) in [(sold_mint, user_sold), (other_mint, user_other)] {
+        let burn_ctx = CpiContext::new(accounts.token_program.to_account_info(), Burn {
+            mint: mint.to_account_info(),
+            from: from.to_account_info(),
+            authority: user.clone(),
+        });
+        token_interface::burn(burn_ctx, sets)?;
+    }
+
+    let market = &accounts.market;
+    let signer_seeds = market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    transfer_tokens(
+        &accounts.collateral_token_program,
+        &accounts.collateral_mint,
+        accounts.collateral_vault.to_account_info(),
+        accounts.user_collateral_account.to_account_info(),
+        market.to_account_info(),
+        &[&market_seeds[..]],
+        collateral_out
+    )?;
+
+    ctx.accounts.pool.apply_swap(side, swap_in, swapped_out)?;
+    emit!(SwapExecuted {
+        market: market_key,
+        user: ctx.accounts.user.key(),
+        side_in: side,
+        amount_in: swap_in,
+        amount_out: swapped_out,
+    });
+    msg!("✅ Sold {} {:?} for {} collateral", sets + swap_in, side, collateral_out);
+    Ok(())
+}
+
+/// Fails unless the market accepts trades: not paused for trading, open and
+/// before expiry.
+fn require_trading_open(market: &mut Market, config: &ProgramConfig) -> Result<()> {
+    config.require_not_paused(market, PAUSE_TRADE)?;
+    market.refresh_status(Clock::get()?.unix_timestamp);
+    match market.status {
+        MarketStatus::Expired => Err(ErrorCode::MarketAlreadyExpired.into()),
+        _ => market.require_status(&[MarketStatus::Open], ErrorCode::MarketNotOpen),
+    }
+}
+
+/// Moves the swap's tokens between user and pool and updates the reserves.
+fn execute_swap(ctx: Context<PoolSwap>, side_in: Side, amount_in: u64, amount_out: u64) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let (mint_in, user_in, pool_in, mint_out, pool_out, user_out) = match side_in {
+        Side::Yes =>
+            (
+                &accounts.yes_mint,
+                &accounts.user_yes_token_account,
+                &accounts.pool_yes_vault,
+                &accounts.no_mint,
+                &accounts.pool_no_vault,
+                &accounts.user_no_token_account,
+            ),
+        Side::No =>
+            (
+                &accounts.no_mint,
+                &accounts.user_no_token_account,
+                &accounts.pool_no_vault,
+                &accounts.yes_mint,
+                &accounts.pool_yes_vault,
+                &accounts.user_yes_token_account,
+            ),
+    };
+
+    transfer_tokens(
+        &accounts.token_program,
+        mint_in,
+        user_in.to_account_info(),
+        pool_in.to_account_info(),
+        accounts.user.to_account_info(),
+        &[],
+        amount_in
+    )?;
+
+    let market_key = accounts.market.key();
+    let pool_seeds = &[b"pool".as_ref(), market_key.as_ref(), &[accounts.pool.bump]];
+    transfer_tokens(
+        &accounts.token_program,
+        mint_out,
+        pool_out.to_account_info(),
+        user_out.to_account_info(),
+        accounts.pool.to_account_info(),
+        &[&pool_seeds[..]],
+        amount_out
+    )?;
+
+    ctx.accounts.pool.apply_swap(side_in, amount_in, amount_out)?;
+    msg!("✅ Swapped {} {:?} for {}", amount_in, side_in, amount_out);
+    emit!(SwapExecuted {
+        market: market_key,
+        user: ctx.accounts.user.key(),
+        side_in,
+        amount_in,
+        amount_out,
+    });
+    Ok(())
+}
+
+/// The `OpenOrders` of `owner` on `market` among `accounts`, if present.
+fn find_open_orders<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    market: &Pubkey,
+    owner: &Pubkey
+) -> Result<Option<&'info AccountInfo<'info>>> {
+    let (address, _) = Pubkey::find_program_address(
+        &[b"open_orders", market.as_ref(), owner.as_ref()],
+        &crate::ID
+    );
+    Ok(accounts.iter().find(|info| info.key() == address))
+}
+
+/// Adds a fill to an `OpenOrders` account. Loads and writes back each call,
+/// so a self-trade crediting the same account twice is safe.
+fn credit_open_orders<'info>(info: &'info AccountInfo<'info>, collateral: u64, yes: u64) -> Result<()> {
+    require!(info.is_writable, ErrorCode::InvalidOpenOrders);
+    let mut open_orders = Account::<OpenOrders>::try_from(info)?;
+    open_orders.collateral_free = open_orders.collateral_free
+        .checked_add(collateral)
+        .ok_or(ErrorCode::Overflow)?;
+    open_orders.yes_free = open_orders.yes_free.checked_add(yes).ok_or(ErrorCode::Overflow)?;
+    open_orders.exit(&crate::ID)
+}
+
+/// Moves `amount` collateral into a market vault, grossed up for any
+/// Token-2022 transfer fee, and checks the vault was credited exactly.
+fn deposit_collateral<'info>(
+    collateral_token_program: &Interface<'info, TokenInterface>,
+    collateral_mint: &InterfaceAccount<'info, Mint>,
+    from: AccountInfo<'info>,
+    vault: &mut InterfaceAccount<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    amount: u64
+) -> Result<()> {
+    let transfer_fee = collateral_transfer_fee(collateral_mint, amount)?;
+    let vault_balance_before = vault.amount;
+    let deposit_ctx = CpiContext::new(collateral_token_program.to_account_info(), TransferChecked {
+        from,
+        mint: collateral_mint.to_account_info(),
+        to: vault.to_account_info(),
+        authority,
+    });
+    token_interface::transfer_checked(
+        deposit_ctx,
+        amount.checked_add(transfer_fee).ok_or(ErrorCode::Overflow)?,
+        collateral_mint.decimals
+    )?;
+
+    vault.reload()?;
+    require!(
+        vault.amount.checked_sub(vault_balance_before) == Some(amount),
+        ErrorCode::CollateralMismatch
+    );
+    Ok(())
+}
+
+fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    amount: u64
+) -> Result<()> {
+    let transfer_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        TransferChecked { from, mint: mint.to_account_info(), to, authority },
+        signer
+    );
+    token_interface::transfer_checked(transfer_ctx, amount, mint.decimals)
+}
+
+/// Constant-product output for `amount_in` after the pool fee, rounded down.
+fn cp_amount_out(amount_in: u64, reserve_in: u64, reserve_out: u64, fee_bps: u16) -> Result<u64> {
+    let in_after_fee = (amount_in as u128) * ((BPS_DENOMINATOR - (fee_bps as u64)) as u128);
+    let numerator = in_after_fee.checked_mul(reserve_out as u128).ok_or(ErrorCode::Overflow)?;
+    let denominator = (reserve_in as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .and_then(|scaled| scaled.checked_add(in_after_fee))
+        .ok_or(ErrorCode::Overflow)?;
+    require!(denominator > 0, ErrorCode::InsufficientLiquidity);
+    Ok(u64::try_from(numerator / denominator).map_err(|_| ErrorCode::Overflow)?)
+}
+
+/// Constant-product input, fee included, needed to take `amount_out`, rounded up.
+fn cp_amount_in(amount_out: u64, reserve_in: u64, reserve_out: u64, fee_bps: u16) -> Result<u64> {
+    require!(amount_out < reserve_out, ErrorCode::InsufficientLiquidity);
+    let numerator = (reserve_in as u128)
+        .checked_mul(amount_out as u128)
+        .and_then(|product| product.checked_mul(BPS_DENOMINATOR as u128))
+        .ok_or(ErrorCode::Overflow)?;
+    let denominator = ((reserve_out - amount_out) as u128) *
+        ((BPS_DENOMINATOR - (fee_bps as u64)) as u128);
+    Ok(u64::try_from(numerator.div_ceil(denominator)).map_err(|_| ErrorCode::Overflow)?)
+}
+
+/// `a * b / c`, rounded down.
+fn mul_div(a: u64, b: u64, c: u64) -> Result<u64> {
+    let result = ((a as u128) * (b as u128)).checked_div(c as u128).ok_or(ErrorCode::Overflow)?;
+    Ok(u64::try_from(result).map_err(|_| ErrorCode::Overflow)?)
+}
+
+/// `a * b / c`, rounded up.
+fn mul_div_ceil(a: u64, b: u64, c: u64) -> Result<u64> {
+    require!(c > 0, ErrorCode::Overflow);
+    let result = ((a as u128) * (b as u128)).div_ceil(c as u128);
+    Ok(u64::try_from(result).map_err(|_| ErrorCode::Overflow)?)
+}
+
+/// Integer square root, rounded down.
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+/// 1.0 in the 18-decimal fixed point used by the LMSR math.
+const WAD: u128 = 1_000_000_000_000_000_000;
+/// ln 2 in `WAD` fixed point.
+const LN2_WAD: u128 = 693_147_180_559_945_309;
+
+/// LMSR cost `b * ln(e^(q_yes/b) + e^(q_no/b))` in `WAD`-scaled outcome
+/// tokens, evaluated as `max(q) + b * ln(1 + e^(-|q_yes - q_no| / b))` so no
+/// intermediate exponent grows. Never below `max(q_yes, q_no)`.
+fn lmsr_cost(b: u64, q_yes: u64, q_no: u64) -> Result<u128> {
+    require!(b > 0, ErrorCode::LmsrDisabled);
+    let (high, low) = (q_yes.max(q_no) as u128, q_yes.min(q_no) as u128);
+    let x = ((high - low) * WAD) / (b as u128);
+    let spread = (b as u128).checked_mul(ln_1p_wad(exp_neg_wad(x))).ok_or(ErrorCode::Overflow)?;
+    high.checked_mul(WAD)
+        .and_then(|base| base.checked_add(spread))
+        .ok_or(ErrorCode::Overflow.into())
+}
+
+/// `WAD`-scaled token amount times `collateral_per_token`, in collateral base units.
+fn wad_to_collateral(tokens_wad: u128, collateral_per_token: u64, round_up: bool) -> Result<u64> {
+    let scaled = tokens_wad.checked_mul(collateral_per_token as u128).ok_or(ErrorCode::Overflow)?;
+    let collateral = if round_up { scaled.div_ceil(WAD) } else { scaled / WAD };
+    Ok(u64::try_from(collateral).map_err(|_| ErrorCode::Overflow)?)
+}
+
+/// `e^(-x)` for `WAD`-scaled `x >= 0`. Reduces `x = k * ln 2 + r` and sums
+/// the Taylor series of `e^(-r)` for `r < ln 2`.
+fn exp_neg_wad(x: u128) -> u128 {
+    // e^-44 is below one WAD unit
+    if x >= 44 * WAD {
+        return 0;
+    }
+    let k = x / LN2_WAD;
+    let r = x - k * LN2_WAD;
+    let mut term = WAD;
+    let mut sum = WAD as i128;
+    let mut n = 1;
+    while term > 0 {
+        term = (term * r) / WAD / n;
+        if n % 2 == 1 {
+            sum -= term as i128;
+        } else {
+            sum += term as i128;
+        }
+        n += 1;
+    }
+    (sum as u128) >> k
+}
+
+/// `ln(1 + y)` for `WAD`-scaled `0 <= y <= 1`, via
+/// `2 * atanh(y / (2 + y))` whose series converges fast for `z <= 1/3`.
+fn ln_1p_wad(y: u128) -> u128 {
+    let z = (y * WAD) / (2 * WAD + y);
+    let z_squared = (z * z) / WAD;
+    let mut power = z;
+    let mut sum = 0;
+    let mut n = 1;
+    while power > 0 {
+        sum += power / n;
+        power = (power * z_squared) / WAD;
+        n += 2;
+    }
+    2 * sum
+}
+
+/// `amount * bps / 10_000`, rounded down.
+fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(ErrorCode::Overflow)? / (BPS_DENOMINATOR as u128);
+    Ok(u64::try_from(fee).map_err(|_| ErrorCode::Overflow)?)
+}
+
+/// Burns `amount` outcome tokens from the user and closes the emptied account.
+fn burn_and_close<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    user_token_account: &InterfaceAccount<'info, TokenAccount>,
+    user: &Signer<'info>,
+    amount: u64
+) -> Result<()> {
+    let burn_ctx = CpiContext::new(token_program.to_account_info(), Burn {
+        mint: mint.to_account_info(),
+        from: user_token_account.to_account_info(),
+        authority: user.to_account_info(),
+    });
+    token_interface::burn(burn_ctx, amount)?;
+
+    let close_ctx = CpiContext::new(token_program.to_account_info(), CloseAccount {
+        account: user_token_account.to_account_info(),
+        destination: user.to_account_info(),
+        authority: user.to_account_info(),
+    });
+    token_interface::close_account(close_ctx)
+}
+
+pub const MAXIMUM_AGE: u64 = 3600; // 1 hour
+pub const FEED_ID: &str = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
+pub const STALENESS_THRESHOLD: u64 = 120; // staleness threshold in seconds
+
+//Doesnt work
+pub fn get_price_feed(ctx: Context<GetPriceFeed>, feed_id_str: String) -> Result<FixedPrice> {
+    msg!("get_price_feed: feed_id_str: {}", feed_id_str);
+
+    let price_update = &mut ctx.accounts.price_update;
+
+    let feed_id = get_feed_id_from_hex(feed_id_str.as_str())?;
+    let price = price_update.get_price_no_older_than(
+        &Clock::get()?,
+        STALENESS_THRESHOLD,
+        &feed_id
+    )?;
+
+    let final_price = FixedPrice::new(price.price as i128, price.exponent);
+
+    msg!("get_price_feed feed_id {:?}, price is {} * 10^{}", feed_id, price.price, price.exponent);
+
+    Ok(final_price)
+}
+
+/// Reads the latest price for a registered asset, enforcing the feed id,
+/// staleness, exponent and confidence limits from its `AssetConfig`.
+pub fn fetch_asset_price(
+    price_account: &Account<PriceUpdateV2>,
+    asset_config: &AssetConfig
+) -> Result<OraclePrice> {
+    // get_price_no_older_than will fail if the price update is for a different price feed.
+    let price = price_account.get_price_no_older_than(
+        &Clock::get()?,
+        asset_config.max_staleness,
+        &asset_config.feed_id
+    )?;
+
+    validate_oracle_price(price, asset_config)
+}
+
+/// Reads the price an expired market settles on: a fully verified update for
+/// the asset's feed whose publish time falls in
+/// `[expiry, expiry + settlement_window]`. The window is capped at
+/// `MAX_SETTLEMENT_WINDOW` (also for configs written before the cap), so a
+/// cranker can only choose among updates published right at expiry.
+pub fn fetch_settlement_price(
+    price_account: &Account<PriceUpdateV2>,
+    asset_config: &AssetConfig,
+    expiry: i64
+) -> Result<OraclePrice> {
+    require!(
+        price_account.verification_level.gte(VerificationLevel::Full),
+        ErrorCode::InvalidPriceAccount
+    );
+    let price = price_account.get_price_unchecked(&asset_config.feed_id)?;
+
+    let window = asset_config.settlement_window.min(MAX_SETTLEMENT_WINDOW);
+    let window_end = expiry
+        .checked_add(i64::try_from(window).map_err(|_| ErrorCode::Overflow)?)
+        .ok_or(ErrorCode::Overflow)?;
+    if price.publish_time < expiry || price.publish_time > window_end {
+        msg!(
+            "Price published at {} is outside the settlement window [{}, {}]",
+            price.publish_time,
+            expiry,
+            window_end
+        );
+        return Err(ErrorCode::PriceOutsideSettlementWindow.into());
+    }
+
+    validate_oracle_price(price, asset_config)
+}
+
+/// Applies the exponent, sign and confidence limits of an `AssetConfig`.
+fn validate_oracle_price(price: Price, asset_config: &AssetConfig) -> Result<OraclePrice> {
+    msg!(
+        "{} price is ({} ± {}) * 10^{}, published at {}",
+        asset_config.symbol,
+        price.price,
+        price.conf,
+        price.exponent,
+        price.publish_time
+    );
+
+    require!(price.exponent == asset_config.price_exponent, ErrorCode::InvalidPriceFeed);
+    require!(price.price > 0, ErrorCode::PriceUnavailable);
+    require!(
+        (price.conf as u128) * 10_000 <=
+            (price.price as u128) * (asset_config.max_confidence_bps as u128),
+        ErrorCode::PriceConfidenceTooWide
+    );
+
+    Ok(OraclePrice {
+        price: FixedPrice::new(price.price as i128, price.exponent),
+        conf: price.conf,
+        publish_time: price.publish_time,
+    })
+}
+
+/// True when `|price - strike| < k * conf`, with `k` in basis points.
+/// Always false when `k_bps` is zero.
+fn is_within_confidence_band(
+    oracle_price: &OraclePrice,
+    strike: &FixedPrice,
+    k_bps: u32
+) -> Result<bool> {
+    if k_bps == 0 {
+        return Ok(false);
+    }
+    let exponent = oracle_price.price.exponent.min(strike.exponent);
+    let distance = oracle_price.price
+        .rescale(exponent)?
+        .checked_sub(strike.rescale(exponent)?)
+        .ok_or(ErrorCode::Overflow)?
+        .unsigned_abs();
+    let conf = FixedPrice::new(oracle_price.conf as i128, oracle_price.price.exponent)
+        .rescale(exponent)?
+        .unsigned_abs();
+
+    let scaled_distance = distance.checked_mul(10_000).ok_or(ErrorCode::Overflow)?;
+    let scaled_band = conf.checked_mul(k_bps as u128).ok_or(ErrorCode::Overflow)?;
+    Ok(scaled_distance < scaled_band)
+}
+
+/// Lists a new underlying. Admin only.
+pub fn add_asset_config(
+    ctx: Context<AddAssetConfig>,
+    asset: u8,
+    params: AssetConfigParams
+) -> Result<()> {
+    let asset_config = &mut ctx.accounts.asset_config;
+    asset_config.asset = asset;
+    asset_config.bump = ctx.bumps.asset_config;
+    apply_asset_config(asset_config, params, true)?;
+
+    msg!("✅ Listed asset {} as {}", asset, asset_config.symbol);
+    Ok(())
+}
+
+/// Updates the feed or limits of a listed underlying, or disables it for new markets. Admin only.
+pub fn update_asset_config(
+    ctx: Context<UpdateAssetConfig>,
+    params: AssetConfigParams,
+    enabled: bool
+) -> Result<()> {
+    let asset_config = &mut ctx.accounts.asset_config;
+    apply_asset_config(asset_config, params, enabled)?;
+
+    msg!("✅ Updated asset {} ({})", asset_config.asset, asset_config.symbol);
+    Ok(())
+}
+
+fn apply_asset_config(
+    asset_config: &mut AssetConfig,
+    params: AssetConfigParams,
+    enabled: bool
+) -> Result<()> {
+    require!(
+        !params.symbol.is_empty() && params.symbol.len() <= MAX_ASSET_SYMBOL_LEN,
+        ErrorCode::InvalidAssetConfig
+    );
+    require!(
+        (MIN_PRICE_EXPONENT..=MAX_PRICE_EXPONENT).contains(&params.price_exponent),
+        ErrorCode::InvalidPriceExponent
+    );
+    require!(params.max_staleness > 0, ErrorCode::InvalidAssetConfig);
+    require!(
+        params.settlement_window > 0 && params.settlement_window <= MAX_SETTLEMENT_WINDOW,
+        ErrorCode::InvalidAssetConfig
+    );
+    require!(
+        params.max_confidence_bps > 0 && params.max_confidence_bps <= 10_000,
+        ErrorCode::InvalidAssetConfig
+    );
+
+    asset_config.feed_id = params.feed_id;
+    asset_config.symbol = params.symbol;
+    asset_config.price_exponent = params.price_exponent;
+    asset_config.max_staleness = params.max_staleness;
+    asset_config.max_confidence_bps = params.max_confidence_bps;
+    asset_config.strike_confidence_k_bps = params.strike_confidence_k_bps;
+    asset_config.settlement_window = params.settlement_window;
+    asset_config.enabled = enabled;
+    Ok(())
+}
+
+/// Creates the program config with the upgrade authority as admin.
+pub fn initialize_config(ctx: Context<InitializeConfig>, fee_recipient: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.admin = ctx.accounts.authority.key();
+    config.pending_admin = None;
+    config.fee_recipient = fee_recipient;
+    config.pause_flags = 0;
+    config.guardian = None;
+    config.deposit_fee_bps = 0;
+    config.redeem_fee_bps = 0;
+    config.max_creator_fee_bps = 0;
+    config.resolver_grace_period = DEFAULT_RESOLVER_GRACE_PERIOD;
+    config.force_resolve_timeout = DEFAULT_FORCE_RESOLVE_TIMEOUT;
+    config.bump = ctx.bumps.config;
+    msg!("✅ Program config initialized, admin: {}", config.admin);
+    Ok(())
+}
+
+/// First step of an admin transfer. Proposing again replaces the pending key.
+pub fn propose_admin(ctx: Context<ConfigAdmin>, new_admin: Pubkey) -> Result<()> {
+    ctx.accounts.config.pending_admin = Some(new_admin);
+    msg!("Proposed new admin: {}", new_admin);
+    Ok(())
+}
+
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.admin = ctx.accounts.new_admin.key();
+    config.pending_admin = None;
+    msg!("✅ Admin transferred to {}", config.admin);
+    Ok(())
+}
+
+pub fn update_config(ctx: Context<ConfigAdmin>, params: ConfigParams) -> Result<()> {
+    require!(
+        params.resolver_grace_period >= 0 &&
+            params.force_resolve_timeout > 0 &&
+            params.pause_flags & !PAUSE_ALL == 0 &&
+            params.deposit_fee_bps <= MAX_PROTOCOL_FEE_BPS &&
+            params.redeem_fee_bps <= MAX_PROTOCOL_FEE_BPS &&
+            params.max_creator_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+        ErrorCode::InvalidConfig
+    );
+    let config = &mut ctx.accounts.config;
+    config.fee_recipient = params.fee_recipient;
+    config.guardian = params.guardian;
+    config.deposit_fee_bps = params.deposit_fee_bps;
+    config.redeem_fee_bps = params.redeem_fee_bps;
+    config.max_creator_fee_bps = params.max_creator_fee_bps;
+    config.pause_flags = params.pause_flags;
+    config.resolver_grace_period = params.resolver_grace_period;
+    config.force_resolve_timeout = params.force_resolve_timeout;
+    msg!("✅ Program config updated");
+    Ok(())
+}
+
+/// Pauses `flags` (`PAUSE_*` bits) program-wide. Admin or guardian.
+pub fn pause(ctx: Context<PauseAuthority>, flags: u8) -> Result<()> {
+    require!(flags != 0 && flags & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+    let config = &mut ctx.accounts.config;
+    config.pause_flags |= flags;
+    msg!("⚠️ Program paused by {}, flags now {:#06b}", ctx.accounts.authority.key(), config.pause_flags);
+    Ok(())
+}
+
+/// Lifts a program-wide pause of `flags`. Admin only.
+pub fn unpause(ctx: Context<ConfigAdmin>, flags: u8) -> Result<()> {
+    require!(flags != 0 && flags & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+    let config = &mut ctx.accounts.config;
+    config.pause_flags &= !flags;
+    msg!("✅ Program unpaused, flags now {:#06b}", config.pause_flags);
+    Ok(())
+}
+
+/// Pauses `flags` on a single market. Admin or guardian.
+pub fn pause_market(ctx: Context<PauseMarket>, flags: u8) -> Result<()> {
+    require!(flags != 0 && flags & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+    let market = &mut ctx.accounts.market;
+    market.pause_flags |= flags;
+    msg!("⚠️ Market paused by {}, flags now {:#06b}", ctx.accounts.authority.key(), market.pause_flags);
+    Ok(())
+}
+
+/// Lifts a pause of `flags` on a single market. Admin only.
+pub fn unpause_market(ctx: Context<AdminMarket>, flags: u8) -> Result<()> {
+    require!(flags != 0 && flags & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+    let market = &mut ctx.accounts.market;
+    market.pause_flags &= !flags;
+    msg!("✅ Market unpaused, flags now {:#06b}", market.pause_flags);
+    Ok(())
+}
+
+/// Creates the fee treasury for a collateral mint. Markets in that mint
+/// cannot take deposits until it exists.
+pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.collateral_mint = ctx.accounts.collateral_mint.key();
+    treasury.total_deposit_fees = 0;
+    treasury.total_redeem_fees = 0;
+    treasury.total_withdrawn = 0;
+    treasury.bump = ctx.bumps.treasury;
+    msg!("✅ Treasury initialized for mint {}", treasury.collateral_mint);
+    Ok(())
+}
+
+/// Sends `amount` collected fees to the config's fee recipient. Admin only.
+pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(ctx.accounts.treasury_vault.amount >= amount, ErrorCode::InsufficientFunds);
+
+    let mint_key = ctx.accounts.collateral_mint.key();
+    let treasury_seeds = &[b"treasury".as_ref(), mint_key.as_ref(), &[ctx.accounts.treasury.bump]];
+    let signer = &[&treasury_seeds[..]];
+    let withdraw_ctx = CpiContext::new_with_signer(
+        ctx.accounts.collateral_token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.treasury_vault.to_account_info(),
+            mint: ctx.accounts.collateral_mint.to_account_info(),
+            to: ctx.accounts.recipient_collateral_account.to_account_info(),
+            authority: ctx.accounts.treasury.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(withdraw_ctx, amount, ctx.accounts.collateral_mint.decimals)?;
+
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.total_withdrawn = treasury.total_withdrawn.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    msg!("✅ Withdrew {} fees to {}", amount, ctx.accounts.config.fee_recipient);
+    Ok(())
+}
+
+pub fn initialize_market(
+    ctx: Context<InitializeMarket>,
+    strike: u64,
+    expiry: i64,
+    asset: u8,
+    params: MarketParams
+) -> Result<()> {
+    validate_market_params(&params, &ctx.accounts.asset_config, &ctx.accounts.config)?;
+    if let Some(range) = params.range {
+        require!(
+            strike < range.upper_strike && params.direction == Direction::AboveOrEqual,
+            ErrorCode::InvalidStrikeRange
+        );
+    }
+    if !params.breakpoints.is_empty() {
+        require!(
+            params.breakpoints.len() <= MAX_BREAKPOINTS &&
+                params.breakpoints[0] == strike &&
+                params.breakpoints.windows(2).all(|pair| pair[0] < pair[1]),
+            ErrorCode::InvalidBreakpoints
+        );
+        // Bucket markets have no YES/NO mints for these to trade, and no
+        // "NO" bucket to fall back to when the band straddles a breakpoint
+        require!(
+            params.range.is_none() &&
+                params.direction == Direction::AboveOrEqual &&
+                params.lmsr_b == 0 &&
+                params.confidence_mode == ConfidenceMode::Reject,
+            ErrorCode::UnsupportedMarketType
+        );
+    }
+    validate_collateral_mint(&ctx.accounts.collateral_mint)?;
+
+    let market = &mut ctx.accounts.market;
+    init_market_state(
+        market,
+        ctx.accounts.authority.key(),
+        strike,
+        expiry,
+        asset,
+        ctx.accounts.collateral_mint.key(),
+        &params
+    );
+
+    // The creator pre-funds the LMSR's worst-case loss, C(0, 0) = b * ln 2 tokens
+    if params.lmsr_b > 0 {
+        let subsidy = wad_to_collateral(lmsr_cost(params.lmsr_b, 0, 0)?, params.collateral_per_token, true)?;
+        let authority_collateral_account = ctx.accounts.authority_collateral_account
+            .as_ref()
+            .ok_or(ErrorCode::MissingCollateralAccount)?;
+        deposit_collateral(
+            &ctx.accounts.collateral_token_program,
+            &ctx.accounts.collateral_mint,
+            authority_collateral_account.to_account_info(),
+            &mut ctx.accounts.collateral_vault,
+            ctx.accounts.authority.to_account_info(),
+            subsidy
+        )?;
+        msg!("LMSR market maker funded with {} collateral (b = {})", subsidy, params.lmsr_b);
+    }
+
+    msg!(
+        "Market initialized! Collateral: {} ({} decimals), {} base units per token",
+        market.collateral_mint,
+        ctx.accounts.collateral_mint.decimals,
+        market.collateral_per_token
+    );
+
+    if market.bucket_count > 0 {
+        msg!("Next step: call create_bucket_mint for each of {} buckets, then open_market", market.bucket_count);
+    } else {
+        msg!("Next step: call create_mint, then open_market");
+    }
+    Ok(())
+}
+
+/// Checks the terms shared by every kind of market.
+fn validate_market_params(params: &MarketParams, asset_config: &AssetConfig, config: &ProgramConfig) -> Result<()> {
+    require!(params.collateral_per_token > 0, ErrorCode::InvalidAmount);
+    require!(
+        (MIN_PRICE_EXPONENT..=MAX_PRICE_EXPONENT).contains(&params.strike_exponent),
+        ErrorCode::InvalidPriceExponent
+    );
+    require!(asset_config.enabled, ErrorCode::InvalidAsset);
+    require!(
+        params.creator_fee_bps <= config.max_creator_fee_bps,
+        ErrorCode::CreatorFeeTooHigh
+    );
+    Ok(())
+}
+
+/// Writes the terms of a freshly created market, leaving it `Created`.
+fn init_market_state(
+    market: &mut Market,
+    authority: Pubkey,
+    strike: u64,
+    expiry: i64,
+    asset: u8,
+    collateral_mint: Pubkey,
+    params: &MarketParams
+) {
+    market.authority = authority;
+    market.strike = strike;
+    market.expiry = expiry;
+    market.asset = asset;
+    market.version = MARKET_VERSION;
+    market.status = MarketStatus::Created;
+    market.outcome = None;
+    market.collateral_mint = collateral_mint;
+    market.collateral_per_token = params.collateral_per_token;
+    market.strike_exponent = params.strike_exponent;
+    market.confidence_mode = params.confidence_mode;
+    market.resolver = params.resolver;
+    market.creator_fee_bps = params.creator_fee_bps;
+    market.direction = params.direction;
+    market.range = params.range;
+    market.bucket_count = if params.breakpoints.is_empty() { 0 } else { (params.breakpoints.len() + 1) as u8 };
+    market.breakpoints = [0; MAX_BREAKPOINTS];
+    market.breakpoints[..params.breakpoints.len()].copy_from_slice(&params.breakpoints);
+    market.winning_bucket = 0;
+    market.bucket_mints_created = 0;
+    market.series = None;
+    market.creator_fees_accrued = 0;
+    market.lmsr_b = params.lmsr_b;
+    market.lmsr_q_yes = 0;
+    market.lmsr_q_no = 0;
+}
+
+/// Creates a strike ladder for one asset and expiry. Markets are listed with
+/// `create_series` and all share `params`, which must describe single-strike
+/// markets.
+pub fn initialize_series(ctx: Context<InitializeSeries>, expiry: i64, asset: u8, params: MarketParams) -> Result<()> {
+    validate_market_params(&params, &ctx.accounts.asset_config, &ctx.accounts.config)?;
+    // `BandMustClear` lets every strike settle from the one observation
+    require!(
+        params.range.is_none() &&
+            params.breakpoints.is_empty() &&
+            params.lmsr_b == 0 &&
+            params.confidence_mode == ConfidenceMode::BandMustClear,
+        ErrorCode::UnsupportedMarketType
+    );
+    require!(Clock::get()?.unix_timestamp < expiry, ErrorCode::MarketAlreadyExpired);
+    validate_collateral_mint(&ctx.accounts.collateral_mint)?;
+
+    let series = &mut ctx.accounts.series;
+    series.authority = ctx.accounts.authority.key();
+    series.asset = asset;
+    series.expiry = expiry;
+    series.collateral_mint = ctx.accounts.collateral_mint.key();
+    series.collateral_per_token = params.collateral_per_token;
+    series.strike_exponent = params.strike_exponent;
+    series.confidence_mode = params.confidence_mode;
+    series.resolver = params.resolver;
+    series.creator_fee_bps = params.creator_fee_bps;
+    series.direction = params.direction;
+    series.strikes = Vec::new();
+    series.settlement = None;
+    series.bump = ctx.bumps.series;
+    msg!("✅ Series initialized for {} expiring at {}", ctx.accounts.asset_config.symbol, expiry);
+    Ok(())
+}
+
+/// Lists up to `MAX_SERIES_BATCH` new strikes in a series. Each market is
+/// created with its collateral vault and YES/NO mints and opened straight
+/// away, so holders only need their own token accounts. Call repeatedly to
+/// build a longer ladder.
+pub fn create_series<'info>(ctx: Context<'_, '_, 'info, 'info, CreateSeries<'info>>, strikes: Vec<u64>) -> Result<()> {
+    let series = &ctx.accounts.series;
+    require!(Clock::get()?.unix_timestamp < series.expiry, ErrorCode::MarketAlreadyExpired);
+    require!(
+        !strikes.is_empty() &&
+            strikes.len() <= MAX_SERIES_BATCH &&
+            series.strikes.len() + strikes.len() <= MAX_SERIES_STRIKES &&
+            strikes.windows(2).all(|pair| pair[0] < pair[1]) &&
+            strikes.iter().all(|strike| !series.strikes.contains(strike)),
+        ErrorCode::InvalidSeriesStrikes
+    );
+    require!(ctx.remaining_accounts.len() == 4 * strikes.len(), ErrorCode::InvalidSeriesMarket);
+
+    let params = series.market_params();
+    let series_key = series.key();
+    let listing = MarketListing {
+        payer: &ctx.accounts.authority,
+        asset_config: &ctx.accounts.asset_config,
+        collateral_mint: &ctx.accounts.collateral_mint,
+        system_program: &ctx.accounts.system_program,
+        token_program: &ctx.accounts.token_program,
+        collateral_token_program: &ctx.accounts.collateral_token_program,
+        associated_token_program: &ctx.accounts.associated_token_program,
+    };
+    for (strike, accounts) in strikes.iter().zip(ctx.remaining_accounts.chunks(4)) {
+        let mut market = list_market(&listing, accounts, series.authority, *strike, series.expiry, series.asset, &params)?;
+        market.series = Some(series_key);
+        market.exit(&crate::ID)?;
+        msg!("✅ Listed strike {} at {}", strike, market.key());
+    }
+
+    let series = &mut ctx.accounts.series;
+    series.strikes.extend_from_slice(&strikes);
+    series.strikes.sort_unstable();
+    msg!("✅ Series now lists {} strikes", series.strikes.len());
+    Ok(())
+}
+
+/// Accounts `list_market` needs besides the new market's own.
+struct MarketListing<'a, 'info> {
+    payer: &'a Signer<'info>,
+    asset_config: &'a AssetConfig,
+    collateral_mint: &'a InterfaceAccount<'info, Mint>,
+    system_program: &'a Program<'info, System>,
+    token_program: &'a Program<'info, Token2022>,
+    collateral_token_program: &'a Interface<'info, TokenInterface>,
+    associated_token_program: &'a Program<'info, associated_token::AssociatedToken>,
+}
+
+/// Does the work of `initialize_market`, `create_mint` and `open_market` for
+/// a single-strike market whose account, collateral vault and YES/NO mints
+/// are passed raw in `accounts`, since their addresses depend on a strike
+/// Anchor's `init` cannot see. The caller persists the returned market.
+fn list_market<'info>(
+    listing: &MarketListing<'_, 'info>,
+    accounts: &'info [AccountInfo<'info>],
+    authority: Pubkey,
+    strike: u64,
+    expiry: i64,
+    asset: u8,
+    params: &MarketParams
+) -> Result<Account<'info, Market>> {
+    let [market_info, vault_info, yes_mint_info, no_mint_info] = accounts else {
+        return Err(ErrorCode::InvalidSeriesMarket.into());
+    };
+    let token_program = listing.token_program;
+
+    // Same address `initialize_market` would give the market
+    let strike_bytes = strike.to_le_bytes();
+    let expiry_bytes = expiry.to_le_bytes();
+    let terms_seed = Market::terms_seed_for(&params.range, &params.breakpoints);
+    let (market_key, market_bump) = Pubkey::find_program_address(
+        &[b"market", authority.as_ref(), &strike_bytes, &expiry_bytes, params.direction.seed(), &terms_seed],
+        &crate::ID
+    );
+    require!(market_info.key() == market_key, ErrorCode::InvalidSeriesMarket);
+    let signer_seeds = MarketSignerSeeds::new(authority, strike, expiry, params.direction, terms_seed, market_bump);
+    let market_seeds = signer_seeds.seeds();
+    let signer: &[&[&[u8]]] = &[&market_seeds[..]];
+
+    create_pda_account(
+        listing.system_program,
+        listing.payer.to_account_info(),
+        market_info.clone(),
+        8 + Market::INIT_SPACE,
+        &crate::ID,
+        signer
+    )?;
+    let mut market = Account::<Market>::try_from_unchecked(market_info)?;
+    init_market_state(&mut market, authority, strike, expiry, asset, listing.collateral_mint.key(), params);
+
+    // Idempotent, as anyone can create the market's vault ATA ahead of us
+    associated_token::create_idempotent(
+        CpiContext::new(listing.associated_token_program.to_account_info(), associated_token::Create {
+            payer: listing.payer.to_account_info(),
+            associated_token: vault_info.clone(),
+            authority: market_info.clone(),
+            mint: listing.collateral_mint.to_account_info(),
+            system_program: listing.system_program.to_account_info(),
+            token_program: listing.collateral_token_program.to_account_info(),
+        })
+    )?;
+
+    // Mirrors the `create_mint` constraints: decimals 0, market as mint
+    // and metadata authority, metadata stored on the mint itself
+    let mint_space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(
+        &[ExtensionType::MetadataPointer]
+    )?;
+    for (mint_info, mint_seed, symbol) in [
+        (yes_mint_info, b"yes_mint".as_ref(), "YES"),
+        (no_mint_info, b"no_mint".as_ref(), "NO"),
+    ] {
+        let (mint_key, mint_bump) = Pubkey::find_program_address(&[mint_seed, market_key.as_ref()], &crate::ID);
+        require!(mint_info.key() == mint_key, ErrorCode::InvalidSeriesMarket);
+        create_pda_account(
+            listing.system_program,
+            listing.payer.to_account_info(),
+            mint_info.clone(),
+            mint_space,
+            &token_program.key(),
+            &[&[mint_seed, market_key.as_ref(), &[mint_bump]]]
+        )?;
+        token_interface::metadata_pointer_initialize(
+            CpiContext::new(token_program.to_account_info(), MetadataPointerInitialize {
+                token_program_id: token_program.to_account_info(),
+                mint: mint_info.clone(),
+            }),
+            Some(market_key),
+            Some(mint_key)
+        )?;
+        token_interface::initialize_mint2(
+            CpiContext::new(token_program.to_account_info(), InitializeMint2 { mint: mint_info.clone() }),
+            0,
+            &market_key,
+            None
+        )?;
+        let mint = InterfaceAccount::<Mint>::try_from(mint_info)?;
+        initialize_outcome_metadata(
+            token_program,
+            &mint,
+            &market,
+            listing.payer,
+            listing.system_program,
+            signer,
+            outcome_token_name(&listing.asset_config.symbol, &market, symbol),
+            symbol.to_string(),
+            "*".to_string()
+        )?;
+    }
+
+    market.status = MarketStatus::Open;
+    Ok(market)
+}
+
+/// Settles the series markets passed as remaining accounts from a single
+/// oracle observation. The first call records it, so later batches settle
+/// on the same price. This is the only oracle path for series markets.
+pub fn resolve_series<'info>(ctx: Context<'_, '_, 'info, 'info, ResolveSeries<'info>>) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let series = &mut ctx.accounts.series;
+    require!(current_time >= series.expiry, ErrorCode::MarketNotExpired);
+    let signer = ctx.accounts.signer.key();
+    let path = resolution_path(
+        series.resolver,
+        &signer,
+        series.expiry,
+        ctx.accounts.config.resolver_grace_period,
+        current_time
+    )?;
+
+    let oracle_price = match series.settlement {
+        Some(observation) => observation,
+        None => {
+            let price_account = ctx.accounts.price_account.as_ref().ok_or(ErrorCode::PriceUnavailable)?;
+            let observation = fetch_settlement_price(price_account, &ctx.accounts.asset_config, series.expiry)?;
+            series.settlement = Some(observation);
+            observation
+        }
+    };
+    msg!(
+        "Series settles on {} * 10^{}",
+        oracle_price.price.mantissa,
+        oracle_price.price.exponent
+    );
+
+    let series_key = series.key();
+    let mut settled = 0;
+    for info in ctx.remaining_accounts {
+        require!(info.is_writable, ErrorCode::InvalidSeriesMarket);
+        let mut market = Account::<Market>::try_from(info)?;
+        require!(market.series == Some(series_key), ErrorCode::InvalidSeriesMarket);
+        ctx.accounts.config.require_not_paused(&market, PAUSE_RESOLVE)?;
+
+        market.refresh_status(current_time);
+        if matches!(market.status, MarketStatus::Resolved | MarketStatus::Voided) {
+            continue;
+        }
+        market.require_status(&[MarketStatus::Expired], ErrorCode::MarketNotOpen)?;
+        // Series markets are `BandMustClear`, which always yields an outcome
+        let Some(outcome) = settlement_outcome(&mut market, &oracle_price, &ctx.accounts.asset_config)? else {
+            return Err(ErrorCode::PriceTooCloseToStrike.into());
+        };
+        record_settlement(&mut market, outcome, &oracle_price, path, signer);
+        market.exit(&crate::ID)?;
+        settled += 1;
+    }
+
+    msg!("✅ Settled {} of the {} markets in the series", settled, series.strikes.len());
+    Ok(())
+}
+
+/// Starts a recurring schedule from `first_market`, an existing single-strike
+/// market of the signer's. Every rolled market copies its terms.
+pub fn initialize_template(ctx: Context<InitializeTemplate>, interval: i64, strike_rule: StrikeRule) -> Result<()> {
+    require!(interval > 0, ErrorCode::InvalidRollInterval);
+    strike_rule.validate()?;
+    let first_market = &ctx.accounts.first_market;
+    require!(
+        first_market.range.is_none() && first_market.bucket_count == 0 && first_market.lmsr_b == 0,
+        ErrorCode::UnsupportedMarketType
+    );
+
+    let template = &mut ctx.accounts.template;
+    template.authority = ctx.accounts.authority.key();
+    template.asset = first_market.asset;
+    template.collateral_mint = first_market.collateral_mint;
+    template.collateral_per_token = first_market.collateral_per_token;
+    template.strike_exponent = first_market.strike_exponent;
+    template.confidence_mode = first_market.confidence_mode;
+    template.resolver = first_market.resolver;
+    template.creator_fee_bps = first_market.creator_fee_bps;
+    template.direction = first_market.direction;
+    template.strike_rule = strike_rule;
+    template.interval = interval;
+    template.current_market = first_market.key();
+    template.rolls = 0;
+    template.bump = ctx.bumps.template;
+    msg!("✅ Template initialized, rolling every {} seconds", interval);
+    Ok(())
+}
+
+/// Permissionless crank: once the template's current market has resolved,
+/// lists the next one with its strike derived from the settlement price and
+/// expiry one interval later. A voided market has no usable settlement, so
+/// its own strike is reused. If the crank runs late, whole intervals are
+/// skipped so the new market still expires in the future.
+pub fn roll_market<'info>(ctx: Context<'_, '_, 'info, 'info, RollMarket<'info>>) -> Result<()> {
+    let template = &ctx.accounts.template;
+    let previous_market = &ctx.accounts.previous_market;
+    previous_market.require_status(
+        &[MarketStatus::Resolved, MarketStatus::Voided],
+        ErrorCode::MarketNotResolved
+    )?;
+
+    let params = template.market_params();
+    validate_market_params(&params, &ctx.accounts.asset_config, &ctx.accounts.config)?;
+    let strike = match previous_market.status {
+        MarketStatus::Resolved =>
+            template.strike_rule.strike_from(&previous_market.settlement_price, template.strike_exponent)?,
+        _ => previous_market.strike,
+    };
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut expiry = previous_market.expiry.checked_add(template.interval).ok_or(ErrorCode::Overflow)?;
+    if expiry <= now {
+        let missed = (now - expiry) / template.interval + 1;
+        expiry = missed
+            .checked_mul(template.interval)
+            .and_then(|skipped| expiry.checked_add(skipped))
+            .ok_or(ErrorCode::Overflow)?;
+    }
+
+    let listing = MarketListing {
+        payer: &ctx.accounts.payer,
+        asset_config: &ctx.accounts.asset_config,
+        collateral_mint: &ctx.accounts.collateral_mint,
+        system_program: &ctx.accounts.system_program,
+        token_program: &ctx.accounts.token_program,
+        collateral_token_program: &ctx.accounts.collateral_token_program,
+        associated_token_program: &ctx.accounts.associated_token_program,
+    };
+    let market = list_market(&listing, ctx.remaining_accounts, template.authority, strike, expiry, template.asset, &params)?;
+    market.exit(&crate::ID)?;
+
+    let template = &mut ctx.accounts.template;
+    template.current_market = market.key();
+    template.rolls = template.rolls.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    msg!("✅ Rolled to strike {} expiring at {} ({})", strike, expiry, market.key());
+    Ok(())
+}
+
+/// Re-points a template at `market`, one of its creator's markets on the
+/// same terms, e.g. when the scheduled market could not be listed or should
+/// be skipped. Template creator only.
+pub fn reset_template(ctx: Context<ResetTemplate>) -> Result<()> {
+    let template = &mut ctx.accounts.template;
+    let market = &ctx.accounts.market;
+    require!(
+        market.asset == template.asset &&
+            market.collateral_mint == template.collateral_mint &&
+            market.collateral_per_token == template.collateral_per_token &&
+            market.strike_exponent == template.strike_exponent &&
+            market.direction == template.direction &&
+            market.range.is_none() &&
+            market.bucket_count == 0,
+        ErrorCode::UnsupportedMarketType
+    );
+
+    template.current_market = market.key();
+    msg!("✅ Template now follows {}", market.key());
+    Ok(())
+}
+
+/// Creates an account at a program-derived address, funded rent-exempt by
+/// `payer`. Like Anchor's `init`, an address someone already sent lamports
+/// to is topped up, allocated and assigned instead, since `create_account`
+/// would refuse it.
+fn create_pda_account<'info>(
+    system_program: &Program<'info, System>,
+    payer: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    signer: &[&[&[u8]]]
+) -> Result<()> {
+    let lamports = Rent::get()?.minimum_balance(space);
+    let current_lamports = account.lamports();
+    if current_lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount { from: payer, to: account },
+                signer
+            ),
+            lamports,
+            space as u64,
+            owner
+        );
+    }
+
+    let rent_due = lamports.saturating_sub(current_lamports);
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(system_program.to_account_info(), system_program::Transfer {
+                from: payer,
+                to: account.clone(),
+            }),
+            rent_due
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Allocate { account_to_allocate: account.clone() },
+            signer
+        ),
+        space as u64
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Assign { account_to_assign: account },
+            signer
+        ),
+        owner
+    )
+}
+
+/// Rewrites a legacy (unversioned) market into the current layout. Legacy
+/// markets settled on whole-unit prices and held their collateral as
+/// lamports on the market account; those lamports stay put, and with no
+/// collateral mint the migrated market can be resolved but not traded.
+pub fn migrate_market(ctx: Context<MigrateMarket>) -> Result<()> {
+    let market = ctx.accounts.market.to_account_info();
+    let legacy_size = 8 + Market::LEGACY_LEN;
+    let new_size = 8 + Market::INIT_SPACE;
+    let legacy = {
+        let data = market.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == Market::DISCRIMINATOR,
+            ErrorCode::InvalidMarketAccount
+        );
+        if data.len() == new_size {
+            msg!("Market is already in the current layout");
+            return Err(ErrorCode::MarketAlreadyMigrated.into());
+        }
+        require!(data.len() == legacy_size, ErrorCode::InvalidMarketAccount);
+        LegacyMarket::deserialize(&mut &data[8..])?
+    };
+
+    let rent_due = Rent::get()?.minimum_balance(new_size).saturating_sub(market.lamports());
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: market.clone(),
+                }
+            ),
+            rent_due
+        )?;
+    }
+    market.realloc(new_size, true)?;
+
+    // Start from an all-zero market, then carry the legacy fields over
+    let mut migrated = Market::try_deserialize_unchecked(&mut &[0u8; 8 + Market::INIT_SPACE][..])?;
+    migrated.authority = legacy.authority;
+    migrated.strike = legacy.strike;
+    migrated.expiry = legacy.expiry;
+    migrated.asset = legacy.asset;
+    migrated.version = MARKET_VERSION;
+    (migrated.status, migrated.outcome) = match (legacy.resolved, legacy.outcome) {
+        (true, Some(1)) => (MarketStatus::Resolved, Some(Outcome::Yes)),
+        (true, Some(2)) => (MarketStatus::Resolved, Some(Outcome::No)),
+        (true, _) => (MarketStatus::Voided, Some(Outcome::Void)),
+        (false, _) => (MarketStatus::Open, None),
+    };
+    migrated.refresh_status(Clock::get()?.unix_timestamp);
+    migrated.try_serialize(&mut &mut market.try_borrow_mut_data()?[..])?;
+
+    msg!("✅ Market migrated to version {}, now {:?}", MARKET_VERSION, migrated.status);
+    Ok(())
+}
+
+pub fn create_mint(
+    ctx: Context<CreateMint>,
+) -> Result<()> {
+    ctx.accounts.market.require_status(&[MarketStatus::Created], ErrorCode::MintsAlreadyCreated)?;
+    require!(ctx.accounts.market.bucket_count == 0, ErrorCode::UnsupportedMarketType);
+
+    // The market PDA is mint authority and metadata update authority for both mints
+    let signer_seeds = ctx.accounts.market.signer_seeds(ctx.bumps.market);
+    let market_seeds = signer_seeds.seeds();
+    let signer: &[&[&[u8]]] = &[&market_seeds[..]];
+
+    let market = &ctx.accounts.market;
+    let asset_name = &ctx.accounts.asset_config.symbol;
+    let uri = "*".to_string();
+
+    let symbol_yes = "YES".to_string();
+    let name_yes = outcome_token_name(asset_name, market, &symbol_yes);
+    initialize_outcome_metadata(
+        &ctx.accounts.token_program,
+        &ctx.accounts.yes_mint,
+        market,
+        &ctx.accounts.authority,
+        &ctx.accounts.system_program,
+        signer,
+        name_yes,
+        symbol_yes,
+        uri.clone()
+    )?;
+
+    //For the no token
+
+    let symbol_no = "NO".to_string();
+    let name_no = outcome_token_name(asset_name, market, &symbol_no);
+    initialize_outcome_metadata(
+        &ctx.accounts.token_program,
+        &ctx.accounts.no_mint,
+        market,
+        &ctx.accounts.authority,
+        &ctx.accounts.system_program,
+        signer,
+        name_no,
+        symbol_no,
+        uri
+    )?;
+
+    ctx.accounts.market.status = MarketStatus::MintsReady;
+    msg!("Succesfully initialized token mint");
+    Ok(())
+}
+
+/// Token name for a YES/NO outcome mint, e.g. `SOL_GTE_150_EXP:1700000000_YES`.
+fn outcome_token_name(asset_symbol: &str, market: &Market, symbol: &str) -> String {
+    let terms = match market.range {
+        None => format!("{}_{}", market.direction.label(), market.strike),
+        Some(range) => format!("IN_{}", range.label(market.strike)),
+    };
+    format!("{}_{}_EXP:{}_{}", asset_symbol, terms, market.expiry, symbol)
+}
+
+/// Writes the Token-2022 metadata extension onto an outcome mint, topping up
+/// the mint's rent for the realloc first.
+#[allow(clippy::too_many_arguments)]
+fn initialize_outcome_metadata<'info>(
+    token_program: &Program<'info, Token2022>,
+    mint: &InterfaceAccount<'info, Mint>,
+    market: &Account<'info, Market>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    signer: &[&[&[u8]]],
+    name: String,
+    symbol: String,
+    uri: String
+) -> Result<()> {
+    let token_metadata = TokenMetadata {
+        update_authority: OptionalNonZeroPubkey::try_from(Some(market.key()))?,
+        mint: mint.key(),
+        name: name.clone(),
+        symbol: symbol.clone(),
+        uri: uri.clone(),
+        additional_metadata: vec![],
+    };
+    let mint_info = mint.to_account_info();
+    let new_len = mint_info.data_len() + token_metadata.tlv_size_of()?;
+    let rent_top_up = Rent::get()?.minimum_balance(new_len).saturating_sub(mint_info.lamports());
+    if rent_top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(system_program.to_account_info(), system_program::Transfer {
+                from: payer.to_account_info(),
+                to: mint_info.clone(),
+            }),
+            rent_top_up
+        )?;
+    }
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        TokenMetadataInitialize {
+            token_program_id: token_program.to_account_info(),
+            metadata: mint_info.clone(), // metadata lives on the mint itself
+            update_authority: market.to_account_info(),
+            mint_authority: market.to_account_info(),
+            mint: mint_info,
+        },
+        signer
+    );
+    token_metadata_initialize(cpi_ctx, name, symbol, uri)
+}
+
+/// Transfer fee the collateral mint charges on a transfer that must credit
+/// exactly `net_amount` to the recipient. Zero for mints without the
+/// Token-2022 transfer-fee extension (including classic SPL mints).
+fn collateral_transfer_fee(collateral_mint: &InterfaceAccount<Mint>, net_amount: u64) -> Result<u64> {
+    let mint_info = collateral_mint.to_account_info();
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    match mint.get_extension::<TransferFeeConfig>() {
+        Ok(fee_config) =>
+            fee_config
+                .calculate_inverse_epoch_fee(Clock::get()?.epoch, net_amount)
+                .ok_or(ErrorCode::Overflow.into()),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Rejects Token-2022 collateral mints whose extensions would let funds leave
+/// the vault outside this program, or that need extra accounts on transfer.
+fn validate_collateral_mint(collateral_mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let mint_info = collateral_mint.to_account_info();
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    for extension in mint.get_extension_types()? {
+        match extension {
+            | ExtensionType::PermanentDelegate
+            | ExtensionType::TransferHook
+            | ExtensionType::NonTransferable
+            | ExtensionType::ConfidentialTransferMint => {
+                msg!("Unsupported collateral mint extension: {:?}", extension);
+                return Err(ErrorCode::UnsupportedCollateralMint.into());
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Outcome of an above-strike market on 150 given a price in 10^-2.
+    fn band_outcome(mode: ConfidenceMode, price: i128, conf: u64, k_bps: u32) -> Option<bool> {
+        let strike = FixedPrice::new(150, 0);
+        let oracle_price = OraclePrice { price: FixedPrice::new(price, -2), conf, publish_time: 0 };
+        let yes = oracle_price.price.checked_cmp(&strike).unwrap().is_ge();
+        let near_strike = is_within_confidence_band(&oracle_price, &strike, k_bps).unwrap();
+        mode.guard(yes, near_strike)
+    }
+
+    #[test]
+    fn band_straddling_the_strike_rejects_or_settles_no() {
+        // 150.50 +- 1.00
+        assert_eq!(band_outcome(ConfidenceMode::Reject, 15_050, 100, 10_000), None);
+        assert_eq!(band_outcome(ConfidenceMode::BandMustClear, 15_050, 100, 10_000), Some(false));
+        assert_eq!(band_outcome(ConfidenceMode::Reject, 14_950, 100, 10_000), None);
+        assert_eq!(band_outcome(ConfidenceMode::BandMustClear, 14_950, 100, 10_000), Some(false));
+    }
+
+    #[test]
+    fn band_clearing_the_strike_settles_on_the_price() {
+        // 151.50 +- 1.00 and 148.50 +- 1.00
+        for mode in [ConfidenceMode::Reject, ConfidenceMode::BandMustClear] {
+            assert_eq!(band_outcome(mode, 15_150, 100, 10_000), Some(true));
+            assert_eq!(band_outcome(mode, 14_850, 100, 10_000), Some(false));
+        }
+    }
+
+    #[test]
+    fn band_edge_touching_the_strike_clears_it() {
+        // 151.00 +- 1.00
+        assert_eq!(band_outcome(ConfidenceMode::Reject, 15_100, 100, 10_000), Some(true));
+    }
+
+    #[test]
+    fn band_scales_with_k_bps() {
+        // 151.50 +- 1.00 straddles at k = 2 but not at k = 1
+        assert_eq!(band_outcome(ConfidenceMode::Reject, 15_150, 100, 20_000), None);
+        assert_eq!(band_outcome(ConfidenceMode::Reject, 15_150, 100, 10_000), Some(true));
+        // 150.50 +- 1.00 clears at k = 0.5
+        assert_eq!(band_outcome(ConfidenceMode::Reject, 15_050, 100, 5_000), Some(true));
+        // k = 0 disables the guard even at the strike
+        assert_eq!(band_outcome(ConfidenceMode::Reject, 15_000, 100, 0), Some(true));
+    }
+
+    #[test]
+    fn band_compares_across_exponents() {
+        let oracle_price = OraclePrice { price: FixedPrice::new(15_000, -2), conf: 60, publish_time: 0 };
+        let strike = FixedPrice::new(1_505_000, -4);
+        assert!(is_within_confidence_band(&oracle_price, &strike, 10_000).unwrap());
+        let oracle_price = OraclePrice { conf: 50, ..oracle_price };
+        assert!(!is_within_confidence_band(&oracle_price, &strike, 10_000).unwrap());
+    }
+
+    #[test]
+    fn resolution_path_reserves_the_grace_period_for_the_resolver() {
+        let resolver = Pubkey::new_unique();
+        let cranker = Pubkey::new_unique();
+        let (expiry, grace) = (1_000, 600);
+        assert_eq!(
+            resolution_path(Some(resolver), &resolver, expiry, grace, expiry).unwrap(),
+            ResolutionPath::Resolver
+        );
+        assert_eq!(
+            resolution_path(Some(resolver), &cranker, expiry, grace, expiry + grace - 1).unwrap_err(),
+            ErrorCode::Unauthorized.into()
+        );
+        assert_eq!(
+            resolution_path(Some(resolver), &cranker, expiry, grace, expiry + grace).unwrap(),
+            ResolutionPath::Permissionless
+        );
+        assert_eq!(
+            resolution_path(Some(resolver), &resolver, expiry, grace, expiry + grace).unwrap(),
+            ResolutionPath::Resolver
+        );
+    }
+
+    #[test]
+    fn resolution_path_without_resolver_is_permissionless() {
+        let cranker = Pubkey::new_unique();
+        assert_eq!(resolution_path(None, &cranker, 1_000, 600, 1_000).unwrap(), ResolutionPath::Permissionless);
+    }
+
+    #[test]
+    fn resolution_path_rejects_grace_overflow() {
+        let cranker = Pubkey::new_unique();
+        assert_eq!(
+            resolution_path(Some(Pubkey::new_unique()), &cranker, i64::MAX, 1, i64::MAX).unwrap_err(),
+            ErrorCode::Overflow.into()
+        );
+    }
+
+    #[test]
+    fn bps_of_rounds_down() {
+        assert_eq!(bps_of(12_345, 30).unwrap(), 37);
+        assert_eq!(bps_of(333, 1).unwrap(), 0);
+        assert_eq!(bps_of(9_999, 1).unwrap(), 0);
+        assert_eq!(bps_of(10_000, 1).unwrap(), 1);
+    }
+
+    #[test]
+    fn bps_of_handles_the_bounds() {
+        assert_eq!(bps_of(12_345, 0).unwrap(), 0);
+        assert_eq!(bps_of(12_345, 10_000).unwrap(), 12_345);
+        assert_eq!(bps_of(u64::MAX, 0).unwrap(), 0);
+        assert_eq!(bps_of(u64::MAX, 30).unwrap(), 55_340_232_221_128_654);
+        assert_eq!(bps_of(u64::MAX, 10_000).unwrap(), u64::MAX);
+        assert_eq!(bps_of(u64::MAX, u16::MAX).unwrap_err(), ErrorCode::Overflow.into());
+    }
+
+    #[test]
+    fn cp_amount_out_rounds_down_and_takes_the_fee() {
+        // 1_000 into 10_000 / 10_000 is 909.09 without a fee, 900.81 at 1%
+        assert_eq!(cp_amount_out(1_000, 10_000, 10_000, 0).unwrap(), 909);
+        assert_eq!(cp_amount_out(1_000, 10_000, 10_000, 100).unwrap(), 900);
+        assert_eq!(cp_amount_out(0, 10_000, 10_000, 100).unwrap(), 0);
+    }
+
+    #[test]
+    fn cp_amount_in_rounds_up_and_takes_the_fee() {
+        // 90 out of 1_000 / 1_000 needs 98.90 without a fee, 99.90 at 1%
+        assert_eq!(cp_amount_in(90, 1_000, 1_000, 0).unwrap(), 99);
+        assert_eq!(cp_amount_in(90, 1_000, 1_000, 100).unwrap(), 100);
+        // Exact quotients are not bumped
+        assert_eq!(cp_amount_in(500, 1_000, 1_000, 0).unwrap(), 1_000);
+        // Rounding always favours the pool
+        for fee_bps in [0, 30, 100] {
+            let amount_in = cp_amount_in(90, 1_000, 1_000, fee_bps).unwrap();
+            assert!(cp_amount_out(amount_in, 1_000, 1_000, fee_bps).unwrap() >= 90);
+        }
+    }
+
+    #[test]
+    fn cp_math_rejects_empty_reserves() {
+        assert_eq!(cp_amount_out(0, 0, 1_000, 0).unwrap_err(), ErrorCode::InsufficientLiquidity.into());
+        assert_eq!(cp_amount_out(1_000, 0, 0, 0).unwrap(), 0);
+        assert_eq!(cp_amount_in(1, 0, 0, 0).unwrap_err(), ErrorCode::InsufficientLiquidity.into());
+        assert_eq!(cp_amount_in(1_000, 1_000, 1_000, 0).unwrap_err(), ErrorCode::InsufficientLiquidity.into());
+    }
+
+    #[test]
+    fn cp_math_rejects_overflow() {
+        assert_eq!(cp_amount_out(u64::MAX, 1, u64::MAX, 0).unwrap_err(), ErrorCode::Overflow.into());
+        assert_eq!(cp_amount_in(u64::MAX - 1, u64::MAX, u64::MAX, 0).unwrap_err(), ErrorCode::Overflow.into());
+        assert_eq!(cp_amount_in(999, u64::MAX / 2, 1_000, 0).unwrap_err(), ErrorCode::Overflow.into());
+    }
+
+    #[test]
+    fn mul_div_rounds_as_named() {
+        assert_eq!(mul_div(7, 3, 2).unwrap(), 10);
+        assert_eq!(mul_div_ceil(7, 3, 2).unwrap(), 11);
+        assert_eq!(mul_div_ceil(6, 3, 2).unwrap(), 9);
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+        assert_eq!(mul_div_ceil(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn mul_div_rejects_zero_divisor_and_overflow() {
+        assert_eq!(mul_div(1, 1, 0).unwrap_err(), ErrorCode::Overflow.into());
+        assert_eq!(mul_div_ceil(1, 1, 0).unwrap_err(), ErrorCode::Overflow.into());
+        assert_eq!(mul_div(u64::MAX, 2, 1).unwrap_err(), ErrorCode::Overflow.into());
+        assert_eq!(mul_div_ceil(u64::MAX, 2, 1).unwrap_err(), ErrorCode::Overflow.into());
+    }
+
+    #[test]
+    fn isqrt_rounds_down() {
+        for (n, root) in [(0, 0), (1, 1), (2, 1), (3, 1), (4, 2), (15, 3), (16, 4), (1_000_000_000_000_000_000, 1_000_000_000)] {
+            assert_eq!(isqrt(n), root);
+        }
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(isqrt((u64::MAX as u128) * (u64::MAX as u128)), u64::MAX as u128);
+    }
+
+    fn assert_close(actual: u128, expected: u128, tolerance: u128) {
+        assert!(actual.abs_diff(expected) <= tolerance, "{} is not within {} of {}", actual, tolerance, expected);
+    }
+
+    #[test]
+    fn exp_neg_wad_matches_known_values() {
+        assert_eq!(exp_neg_wad(0), WAD);
+        assert_close(exp_neg_wad(LN2_WAD), WAD / 2, 1_000);
+        // e^-1 and e^-10
+        assert_close(exp_neg_wad(WAD), 367_879_441_171_442_321, 1_000);
+        assert_close(exp_neg_wad(10 * WAD), 45_399_929_762_484, 1_000);
+        assert_eq!(exp_neg_wad(44 * WAD), 0);
+    }
+
+    #[test]
+    fn ln_1p_wad_matches_known_values() {
+        assert_eq!(ln_1p_wad(0), 0);
+        assert_close(ln_1p_wad(WAD), LN2_WAD, 1_000);
+        // ln 1.5
+        assert_close(ln_1p_wad(WAD / 2), 405_465_108_108_164_381, 1_000);
+    }
+
+    #[test]
+    fn lmsr_cost_of_balanced_book_is_max_plus_b_ln2() {
+        assert_close(lmsr_cost(100, 0, 0).unwrap(), 100 * LN2_WAD, 100_000);
+        assert_close(lmsr_cost(100, 40, 40).unwrap(), 40 * WAD + 100 * LN2_WAD, 100_000);
+    }
+
+    #[test]
+    fn lmsr_cost_is_symmetric_and_bounded() {
+        let b = 1_000;
+        let cost = lmsr_cost(b, 700, 200).unwrap();
+        assert_eq!(cost, lmsr_cost(b, 200, 700).unwrap());
+        // max(q) <= C <= max(q) + b ln 2
+        assert!(cost >= 700 * WAD);
+        assert!(cost <= 700 * WAD + (b as u128) * LN2_WAD);
+        // Buying more YES always costs something
+        assert!(lmsr_cost(b, 701, 200).unwrap() > cost);
+    }
+
+    #[test]
+    fn lmsr_cost_requires_liquidity() {
+        assert!(lmsr_cost(0, 1, 1).is_err());
+    }
+
+    #[test]
+    fn wad_to_collateral_rounds_as_asked() {
+        let half_token = WAD / 2;
+        assert_eq!(wad_to_collateral(half_token, 3, false).unwrap(), 1);
+        assert_eq!(wad_to_collateral(half_token, 3, true).unwrap(), 2);
+        assert_eq!(wad_to_collateral(2 * WAD, 3, true).unwrap(), 6);
+    }
+}
@@ -1,4 +1,4 @@
-use anchor_lang::{prelude::*, accounts::account::Account};
+use anchor_lang::prelude::*;
 use crate::state::*;
 
 
@@ -20,59 +20,242 @@ pub mod binary_options {
         strike: u64,
         expiry: i64,
         asset: u8,
+        params: MarketParams,
     ) -> Result<()> {
-        instructions::initialize_market(ctx, strike, expiry, asset)
+        instructions::initialize_market(ctx, strike, expiry, asset, params)
     }
 
     pub fn resolve_market(ctx: Context<ResolveMarket>) -> Result<()> {
         instructions::resolve_market(ctx)
     }
+
+    pub fn force_resolve(ctx: Context<AdminMarket>, outcome: Outcome, price: FixedPrice) -> Result<()> {
+        instructions::force_resolve(ctx, outcome, price)
+    }
+
+    pub fn cancel_market(ctx: Context<CancelMarket>) -> Result<()> {
+        instructions::cancel_market(ctx)
+    }
+
+    pub fn halt_market(ctx: Context<AdminMarket>) -> Result<()> {
+        instructions::halt_market(ctx)
+    }
+
+    pub fn resume_market(ctx: Context<AdminMarket>) -> Result<()> {
+        instructions::resume_market(ctx)
+    }
+
+    pub fn open_market(ctx: Context<MarketAuthority>) -> Result<()> {
+        instructions::open_market(ctx)
+    }
+
+    pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>) -> Result<()> {
+        instructions::claim_creator_fees(ctx)
+    }
+
+    pub fn initialize_pool(ctx: Context<InitializePool>, fee_bps: u16) -> Result<()> {
+        instructions::initialize_pool(ctx, fee_bps)
+    }
+
+    pub fn add_liquidity(ctx: Context<PoolLiquidity>, max_yes: u64, max_no: u64, min_lp: u64) -> Result<()> {
+        instructions::add_liquidity(ctx, max_yes, max_no, min_lp)
+    }
+
+    pub fn remove_liquidity(ctx: Context<PoolLiquidity>, lp_amount: u64, min_yes: u64, min_no: u64) -> Result<()> {
+        instructions::remove_liquidity(ctx, lp_amount, min_yes, min_no)
+    }
+
+    pub fn redeem_pool_reserves(ctx: Context<PoolReserves>) -> Result<()> {
+        instructions::redeem_pool_reserves(ctx)
+    }
+
+    pub fn swap_exact_in(ctx: Context<PoolSwap>, side_in: Side, amount_in: u64, min_out: u64) -> Result<()> {
+        instructions::swap_exact_in(ctx, side_in, amount_in, min_out)
+    }
+
+    pub fn swap_exact_out(ctx: Context<PoolSwap>, side_in: Side, amount_out: u64, max_in: u64) -> Result<()> {
+        instructions::swap_exact_out(ctx, side_in, amount_out, max_in)
+    }
+
+    pub fn lmsr_buy(ctx: Context<LmsrTrade>, side: Side, amount: u64, max_cost: u64) -> Result<()> {
+        instructions::lmsr_buy(ctx, side, amount, max_cost)
+    }
+
+    pub fn lmsr_sell(ctx: Context<LmsrTrade>, side: Side, amount: u64, min_proceeds: u64) -> Result<()> {
+        instructions::lmsr_sell(ctx, side, amount, min_proceeds)
+    }
+
+    pub fn reclaim_lmsr_subsidy(ctx: Context<ReclaimLmsrSubsidy>) -> Result<()> {
+        instructions::reclaim_lmsr_subsidy(ctx)
+    }
+
+    pub fn initialize_order_book(ctx: Context<InitializeOrderBook>, min_order_size: u64) -> Result<()> {
+        instructions::initialize_order_book(ctx, min_order_size)
+    }
+
+    pub fn place_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ManageOrders<'info>>,
+        side: OrderSide,
+        price: u64,
+        quantity: u64
+    ) -> Result<()> {
+        instructions::place_order(ctx, side, price, quantity)
+    }
+
+    pub fn cancel_order(ctx: Context<ManageOrders>, order_id: u64) -> Result<()> {
+        instructions::cancel_order(ctx, order_id)
+    }
+
+    pub fn match_orders(ctx: Context<MatchOrders>, limit: u8) -> Result<()> {
+        instructions::match_orders(ctx, limit)
+    }
+
+    pub fn consume_events<'info>(ctx: Context<'_, '_, 'info, 'info, ConsumeEvents<'info>>, limit: u8) -> Result<()> {
+        instructions::consume_events(ctx, limit)
+    }
+
+    pub fn settle_funds(ctx: Context<ManageOrders>) -> Result<()> {
+        instructions::settle_funds(ctx)
+    }
+
+    pub fn buy_yes(ctx: Context<PoolTrade>, collateral_in: u64, min_yes_out: u64) -> Result<()> {
+        instructions::buy_yes(ctx, collateral_in, min_yes_out)
+    }
+
+    pub fn buy_no(ctx: Context<PoolTrade>, collateral_in: u64, min_no_out: u64) -> Result<()> {
+        instructions::buy_no(ctx, collateral_in, min_no_out)
+    }
+
+    pub fn sell_yes(ctx: Context<PoolTrade>, yes_in: u64, min_collateral_out: u64) -> Result<()> {
+        instructions::sell_yes(ctx, yes_in, min_collateral_out)
+    }
+
+    pub fn sell_no(ctx: Context<PoolTrade>, no_in: u64, min_collateral_out: u64) -> Result<()> {
+        instructions::sell_no(ctx, no_in, min_collateral_out)
+    }
+
+    pub fn initialize_series(ctx: Context<InitializeSeries>, expiry: i64, asset: u8, params: MarketParams) -> Result<()> {
+        instructions::initialize_series(ctx, expiry, asset, params)
+    }
+
+    pub fn create_series<'info>(ctx: Context<'_, '_, 'info, 'info, CreateSeries<'info>>, strikes: Vec<u64>) -> Result<()> {
+        instructions::create_series(ctx, strikes)
+    }
+
+    pub fn resolve_series<'info>(ctx: Context<'_, '_, 'info, 'info, ResolveSeries<'info>>) -> Result<()> {
+        instructions::resolve_series(ctx)
+    }
+
+    pub fn initialize_template(ctx: Context<InitializeTemplate>, interval: i64, strike_rule: StrikeRule) -> Result<()> {
+        instructions::initialize_template(ctx, interval, strike_rule)
+    }
+
+    pub fn roll_market<'info>(ctx: Context<'_, '_, 'info, 'info, RollMarket<'info>>) -> Result<()> {
+        instructions::roll_market(ctx)
+    }
+
+    pub fn reset_template(ctx: Context<ResetTemplate>) -> Result<()> {
+        instructions::reset_template(ctx)
+    }
+
+    pub fn close_market<'info>(ctx: Context<'_, '_, 'info, 'info, CloseMarket<'info>>) -> Result<()> {
+        instructions::close_market(ctx)
+    }
+
+    pub fn migrate_market(ctx: Context<MigrateMarket>) -> Result<()> {
+        instructions::migrate_market(ctx)
+    }
+    pub fn initialize_config(ctx: Context<InitializeConfig>, fee_recipient: Pubkey) -> Result<()> {
+        instructions::initialize_config(ctx, fee_recipient)
+    }
+
+    pub fn propose_admin(ctx: Context<ConfigAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
+
+    pub fn update_config(ctx: Context<ConfigAdmin>, params: ConfigParams) -> Result<()> {
+        instructions::update_config(ctx, params)
+    }
+
+    pub fn pause(ctx: Context<PauseAuthority>, flags: u8) -> Result<()> {
+        instructions::pause(ctx, flags)
+    }
+
+    pub fn unpause(ctx: Context<ConfigAdmin>, flags: u8) -> Result<()> {
+        instructions::unpause(ctx, flags)
+    }
+
+    pub fn pause_market(ctx: Context<PauseMarket>, flags: u8) -> Result<()> {
+        instructions::pause_market(ctx, flags)
+    }
+
+    pub fn unpause_market(ctx: Context<AdminMarket>, flags: u8) -> Result<()> {
+        instructions::unpause_market(ctx, flags)
+    }
+
     pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
         instructions::initialize_treasury(ctx)
     }
+
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        instructions::withdraw_fees(ctx, amount)
+    }
     // pub fn create_outcome_tokens<'info>(ctx: Context<'_, '_, '_, 'info, CreateOutcomeTokens<'info>>) -> Result<()>{
     //     instructions::create_outcome_tokens(ctx)
     // }
 
-    pub fn initialize_outcome_mints(ctx: Context<InitializeOutcomeMints>)->Result<()>{
-        instructions::initialize_outcome_mints(ctx)
+    pub fn mint_complete_set(ctx: Context<MintCompleteSet>, amount: u64)->Result<()>{
+        instructions::mint_complete_set(ctx, amount)
+    }
+
+    pub fn burn_complete_set(ctx: Context<BurnCompleteSet>, amount: u64)->Result<()>{
+        instructions::burn_complete_set(ctx, amount)
     }
-    pub fn mint_outcome_tokens(ctx: Context<MintOutcomeTokens>)->Result<()>{
-        instructions::mint_outcome_tokens(ctx)
+
+    pub fn create_bucket_mint(ctx: Context<CreateBucketMint>, index: u8) -> Result<()> {
+        instructions::create_bucket_mint(ctx, index)
     }
-    pub fn initialize_treasury_token_accounts(ctx: Context<InitializeTreasuryTokenAccounts>)->Result<()>{
-        instructions::initialize_treasury_token_accounts(ctx)
+
+    pub fn mint_bucket_set<'info>(ctx: Context<'_, '_, 'info, 'info, MintBucketSet<'info>>, amount: u64) -> Result<()> {
+        instructions::mint_bucket_set(ctx, amount)
     }
 
-    pub fn lock_funds(ctx: Context<LockFunds>, amount: u64)->Result<()>{
-        instructions::lock_funds(ctx, amount)
+    pub fn redeem_bucket(ctx: Context<RedeemBucket>, index: u8) -> Result<()> {
+        instructions::redeem_bucket(ctx, index)
     }
 
-    pub fn get_price_feed(ctx: Context<GetPriceFeed>, feed_id_str: String) -> Result<f64> {
+    pub fn get_price_feed(ctx: Context<GetPriceFeed>, feed_id_str: String) -> Result<FixedPrice> {
         instructions::get_price_feed(ctx, feed_id_str)
     }
 
-    pub fn fetch_coin_price(ctx: Context<FetchCoinPrice>, coin: i8) -> Result<f64> {
-        
-        match coin {   
-        1=>  return instructions::fetch_btc_price(&ctx.accounts.price_update),
-        2 =>  return instructions::fetch_sol_price(&ctx.accounts.price_update),
-        3 => return instructions::fetch_eth_price(&ctx.accounts.price_update),
-        _ => return Err(error::ErrorCode::InvalidCoin.into())
-            
-        }
+    pub fn fetch_asset_price(ctx: Context<FetchAssetPrice>) -> Result<OraclePrice> {
+        instructions::fetch_asset_price(&ctx.accounts.price_update, &ctx.accounts.asset_config)
+    }
+
+    pub fn add_asset_config(
+        ctx: Context<AddAssetConfig>,
+        asset: u8,
+        params: AssetConfigParams,
+    ) -> Result<()> {
+        instructions::add_asset_config(ctx, asset, params)
     }
 
-    pub fn fetch_btc_price(ctx: Context<FetchCoinPrice>) -> Result<f64> {
-        instructions::fetch_btc_price(&ctx.accounts.price_update)
+    pub fn update_asset_config(
+        ctx: Context<UpdateAssetConfig>,
+        params: AssetConfigParams,
+        enabled: bool,
+    ) -> Result<()> {
+        instructions::update_asset_config(ctx, params, enabled)
     }
+
     pub fn redeem(ctx: Context<Redeem>)->Result<()>{
         instructions::redeem(ctx)
     }
 
-    pub fn mint_metadata_tokens(ctx: Context<MintMetadataTokens>)->Result<()>{
-        instructions::mint_metadata_tokens(ctx)
-    }
     pub fn create_mint(ctx: Context<CreateMint>)->Result<()>{
        instructions::create_mint(ctx) 
     }
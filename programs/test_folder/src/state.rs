@@ -1,68 +1,1018 @@
 use anchor_lang::prelude::*;
 
-use solana_program::{ pubkey, pubkey::Pubkey };
+use solana_program::pubkey::Pubkey;
+use solana_program::hash::hashv;
+use std::cmp::Ordering;
 use solana_program::system_program;
 use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    metadata::{
-        create_metadata_accounts_v3,
-        CreateMetadataAccountsV3,
-        MasterEditionAccount,
-        Metadata,
-    },
-    token::{ burn, mint_to, Burn, Mint, MintTo, Token, TokenAccount },
+    token_2022::Token2022,
+    token_interface::{ Mint, TokenAccount, TokenInterface },
 };
 
-use mpl_token_metadata::accounts::MasterEdition;
-use mpl_token_metadata::{ types::DataV2 };
-use mpl_token_metadata::ID as METAPLEX_PROGRAM_ID;
+use crate::error::ErrorCode;
 
 /// The primary Market account structure.
 /// This stores all relevant metadata for the binary option market.
+///
+/// New fields are carved out of `reserved` (shrinking it by the same number
+/// of bytes) so the account size never changes; bump `MARKET_VERSION` when
+/// the meaning of existing bytes changes.
 #[account]
+#[derive(InitSpace)]
 pub struct Market {
     /// The wallet (Pubkey) who created the market
     pub authority: Pubkey,
 
-    /// The strike price for SOL/USD or another asset.
+    /// The strike price for SOL/USD or another asset, as a mantissa scaled
+    /// by `strike_exponent` (strike = strike * 10^strike_exponent).
     pub strike: u64,
 
     /// Expiration timestamp (Unix time, e.g., seconds since epoch)
     pub expiry: i64,
-    //better implemenataion later
-    /// Address of the oracle feed (Chainlink or Pyth) providing price data
-    //pub oracle_feed: Pubkey,
+    /// Id of the `AssetConfig` registry entry holding the oracle feed
+    pub asset: u8,
+
+    /// Lifecycle stage; every instruction checks it before acting
+    pub status: MarketStatus,
+
+    /// Outcome after resolution, `None` until resolved or voided
+    pub outcome: Option<Outcome>,
+
+    /// SPL mint the market is collateralised in (e.g. USDC)
+    pub collateral_mint: Pubkey,
+
+    /// Collateral base units locked per complete set, and paid per winning token
+    pub collateral_per_token: u64,
+
+    /// Decimal exponent of `strike`, e.g. -2 for a strike quoted in cents
+    pub strike_exponent: i32,
+
+    /// What resolution does when the oracle confidence band straddles the strike
+    pub confidence_mode: ConfidenceMode,
+
+    /// Oracle price the market settled on (zero until resolved)
+    pub settlement_price: FixedPrice,
+
+    /// Confidence interval of the settlement price, in `settlement_price.exponent`
+    pub settlement_conf: u64,
+
+    /// Pyth publish time of the settlement price
+    pub settlement_publish_time: i64,
+
+    /// Designated resolver. During the grace period after expiry only this key
+    /// may resolve; with `None` resolution is permissionless from expiry.
+    pub resolver: Option<Pubkey>,
+
+    /// Layout version, `MARKET_VERSION` for accounts created or migrated by
+    /// this program. Unversioned accounts are `LEGACY_LEN` bytes long.
+    pub version: u8,
+
+    /// Operations paused on this market only, `PAUSE_*` bits
+    pub pause_flags: u8,
+
+    /// Creator fee charged on top of collateral locked in this market, in bps
+    pub creator_fee_bps: u16,
+
+    /// Creator fees held in the collateral vault and not yet claimed
+    pub creator_fees_accrued: u64,
+
+    /// LMSR liquidity parameter in outcome tokens, zero when the market has
+    /// no LMSR market maker. The creator's loss is bounded by `b * ln 2` tokens.
+    pub lmsr_b: u64,
+
+    /// YES tokens net sold by the LMSR market maker
+    pub lmsr_q_yes: u64,
+
+    /// NO tokens net sold by the LMSR market maker
+    pub lmsr_q_no: u64,
+
+    /// Which side of the strike settles YES, and how ties settle
+    pub direction: Direction,
+
+    /// Upper bound for range markets, which settle YES when the price lands
+    /// between `strike` and `range.upper_strike`. `None` for single-strike markets.
+    pub range: Option<StrikeRange>,
+
+    /// Number of outcome buckets for bucket markets, zero for YES/NO markets.
+    /// Bucket `i` pays when `breakpoints[i - 1] <= price < breakpoints[i]`.
+    pub bucket_count: u8,
+
+    /// Ascending bucket boundaries in `strike_exponent`; the first
+    /// `bucket_count - 1` are used and `breakpoints[0] == strike`
+    pub breakpoints: [u64; MAX_BREAKPOINTS],
+
+    /// Bucket whose mint pays out, valid once a bucket market resolves `Yes`
+    pub winning_bucket: u8,
+
+    /// Bucket mints created so far; the market is `MintsReady` once all exist
+    pub bucket_mints_created: u8,
+
+    /// `MarketSeries` the market was listed in by `create_series`, if any
+    pub series: Option<Pubkey>,
+
+    /// Zeroed space for future fields
+    pub reserved: [u8; MARKET_RESERVED_BYTES],
+}
+
+/// Current `Market` layout version.
+pub const MARKET_VERSION: u8 = 1;
+
+/// Protocol fees collected in one collateral mint. Fees sit in the
+/// treasury's associated token account until `withdraw_fees`.
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub collateral_mint: Pubkey,
+    /// Lifetime fees taken on `mint_complete_set`
+    pub total_deposit_fees: u64,
+    /// Lifetime fees taken on winning `redeem` payouts
+    pub total_redeem_fees: u64,
+    /// Lifetime fees paid out by `withdraw_fees`
+    pub total_withdrawn: u64,
+    pub bump: u8,
+}
 
-    //1 for btc, 2 for sol, 3 for eth
+/// Constant-product YES/NO pool for a market. Reserves are held in the pool's
+/// associated token accounts; LPs own the pool through `lp_mint`. Swap fees
+/// stay in the reserves and accrue to LPs.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub market: Pubkey,
+    pub lp_mint: Pubkey,
+    pub yes_reserve: u64,
+    pub no_reserve: u64,
+    /// Swap fee on the input amount, in bps
+    pub fee_bps: u16,
+    pub bump: u8,
+}
+
+impl Pool {
+    pub fn reserves(&self, side_in: Side) -> (u64, u64) {
+        match side_in {
+            Side::Yes => (self.yes_reserve, self.no_reserve),
+            Side::No => (self.no_reserve, self.yes_reserve),
+        }
+    }
+
+    /// Adds `amount_in` to the `side_in` reserve and takes `amount_out` from the other.
+    pub fn apply_swap(&mut self, side_in: Side, amount_in: u64, amount_out: u64) -> Result<()> {
+        let (reserve_in, reserve_out) = match side_in {
+            Side::Yes => (&mut self.yes_reserve, &mut self.no_reserve),
+            Side::No => (&mut self.no_reserve, &mut self.yes_reserve),
+        };
+        *reserve_in = reserve_in.checked_add(amount_in).ok_or(ErrorCode::Overflow)?;
+        *reserve_out = reserve_out.checked_sub(amount_out).ok_or(ErrorCode::InsufficientLiquidity)?;
+        Ok(())
+    }
+}
+
+/// Side of a limit order on a market's YES order book.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderSide {
+    /// Buy YES, escrowing `price * quantity` collateral
+    Bid,
+    /// Sell YES, escrowing `quantity` YES
+    Ask,
+}
+
+/// A resting limit order. `price` is collateral base units per YES token,
+/// strictly between 0 and `Market.collateral_per_token`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug)]
+pub struct Order {
+    pub order_id: u64,
+    pub owner: Pubkey,
+    pub price: u64,
+    pub quantity: u64,
+}
+
+/// Resting orders kept per side of an `OrderBook`.
+pub const MAX_ORDERS_PER_SIDE: usize = 32;
+
+/// Unconsumed fills an `EventQueue` can hold.
+pub const MAX_FILL_EVENTS: usize = 64;
+
+/// Per-market limit order book for YES against collateral. Bids are kept
+/// best (highest) price first and asks lowest first, FIFO within a price.
+/// Escrow sits in market-owned `book_collateral`/`book_yes` token accounts.
+/// Once a side is full, a better-priced order evicts the worst one, whose
+/// escrow is credited back to its owner's `OpenOrders`.
+#[account]
+#[derive(InitSpace)]
+pub struct OrderBook {
+    pub market: Pubkey,
+    pub next_order_id: u64,
+    #[max_len(MAX_ORDERS_PER_SIDE)]
+    pub bids: Vec<Order>,
+    #[max_len(MAX_ORDERS_PER_SIDE)]
+    pub asks: Vec<Order>,
+    pub bump: u8,
+    /// Smallest quantity, in YES, an order may be placed with
+    pub min_order_size: u64,
+}
+
+impl OrderBook {
+    /// Inserts `order` behind every order at the same or a better price.
+    pub fn insert(&mut self, side: OrderSide, order: Order) -> Result<()> {
+        let orders = match side {
+            OrderSide::Bid => &mut self.bids,
+            OrderSide::Ask => &mut self.asks,
+        };
+        require!(orders.len() < MAX_ORDERS_PER_SIDE, ErrorCode::OrderBookFull);
+        let position = orders
+            .iter()
+            .position(|resting| match side {
+                OrderSide::Bid => resting.price < order.price,
+                OrderSide::Ask => resting.price > order.price,
+            })
+            .unwrap_or(orders.len());
+        orders.insert(position, order);
+        Ok(())
+    }
+
+    /// If `side` is full and `price` beats its worst order, removes and
+    /// returns that order to make room.
+    pub fn evict_worst(&mut self, side: OrderSide, price: u64) -> Option<Order> {
+        let orders = match side {
+            OrderSide::Bid => &mut self.bids,
+            OrderSide::Ask => &mut self.asks,
+        };
+        let worst = orders.last()?;
+        let beats_worst = match side {
+            OrderSide::Bid => price > worst.price,
+            OrderSide::Ask => price < worst.price,
+        };
+        if orders.len() < MAX_ORDERS_PER_SIDE || !beats_worst {
+            return None;
+        }
+        orders.pop()
+    }
+
+    /// Removes and returns the order with `order_id`.
+    pub fn remove(&mut self, order_id: u64) -> Option<(OrderSide, Order)> {
+        if let Some(index) = self.bids.iter().position(|order| order.order_id == order_id) {
+            return Some((OrderSide::Bid, self.bids.remove(index)));
+        }
+        if let Some(index) = self.asks.iter().position(|order| order.order_id == order_id) {
+            return Some((OrderSide::Ask, self.asks.remove(index)));
+        }
+        None
+    }
+
+    /// Takes `quantity` off the best order of `side`, dropping it once empty.
+    pub fn fill_best(&mut self, side: OrderSide, quantity: u64) {
+        let orders = match side {
+            OrderSide::Bid => &mut self.bids,
+            OrderSide::Ask => &mut self.asks,
+        };
+        orders[0].quantity -= quantity;
+        if orders[0].quantity == 0 {
+            orders.remove(0);
+        }
+    }
+}
+
+/// A match between a bid and an ask, waiting for `consume_events` to credit
+/// both owners' `OpenOrders`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug)]
+pub struct FillEvent {
+    pub bid_owner: Pubkey,
+    pub ask_owner: Pubkey,
+    /// Trade price, set by whichever order rested first
+    pub price: u64,
+    /// Limit price of the bid; the bidder is refunded the difference
+    pub bid_price: u64,
+    pub quantity: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct EventQueue {
+    pub market: Pubkey,
+    #[max_len(MAX_FILL_EVENTS)]
+    pub events: Vec<FillEvent>,
+    pub bump: u8,
+}
+
+/// A user's settled-but-unwithdrawn order book balances for one market.
+#[account]
+#[derive(InitSpace)]
+pub struct OpenOrders {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    /// Collateral from filled asks and bid price improvement
+    pub collateral_free: u64,
+    /// YES bought by filled bids
+    pub yes_free: u64,
+    pub bump: u8,
+}
+
+/// Upper bound on `Pool.fee_bps`.
+pub const MAX_POOL_FEE_BPS: u16 = 1_000;
+
+/// LP shares minted to the pool's own LP account on the first deposit and
+/// never redeemable, so the LP supply can't be shrunk to a few shares worth
+/// a large reserve each.
+pub const MINIMUM_LIQUIDITY: u64 = 100;
+
+#[event]
+pub struct SwapExecuted {
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub side_in: Side,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+/// Bytes after `Market.version`, shared by the fields added since and `reserved`.
+pub const MARKET_VERSIONED_SPACE: usize = 256;
+
+/// Size of the versioned `Market` layout (excluding discriminator): the
+/// legacy layout, the fields added before `version`, `version` itself and
+/// the fixed space behind it.
+pub const MARKET_ACCOUNT_SPACE: usize = Market::LEGACY_LEN +
+    // collateral_mint, collateral_per_token, strike_exponent, confidence_mode
+    32 + 8 + 4 + ConfidenceMode::INIT_SPACE +
+    // settlement_price, settlement_conf, settlement_publish_time, resolver
+    FixedPrice::INIT_SPACE + 8 + 8 + (1 + 32) +
+    // version
+    1 +
+    MARKET_VERSIONED_SPACE;
+
+/// Bytes of `Market.reserved` left for future fields.
+pub const MARKET_RESERVED_BYTES: usize = 117;
+
+/// Most strikes a `MarketSeries` can list.
+pub const MAX_SERIES_STRIKES: usize = 32;
+
+/// Most strikes `create_series` creates per call, keeping each batch
+/// (four accounts and a dozen CPIs per strike) within compute and account limits.
+pub const MAX_SERIES_BATCH: usize = 4;
+
+/// A ladder of single-strike markets sharing an asset, expiry and terms.
+/// Every market is settled by `resolve_series` from the one oracle
+/// observation the series records, so series are always `BandMustClear`.
+#[account]
+#[derive(InitSpace)]
+pub struct MarketSeries {
+    /// Creator of the series, and `authority` of every market in it
+    pub authority: Pubkey,
     pub asset: u8,
+    pub expiry: i64,
+    pub collateral_mint: Pubkey,
+    pub collateral_per_token: u64,
+    pub strike_exponent: i32,
+    pub confidence_mode: ConfidenceMode,
+    pub resolver: Option<Pubkey>,
+    pub creator_fee_bps: u16,
+    pub direction: Direction,
+    /// Strikes listed so far, ascending
+    #[max_len(MAX_SERIES_STRIKES)]
+    pub strikes: Vec<u64>,
+    /// Oracle observation every market in the series settles on, recorded
+    /// by the first `resolve_series` call
+    pub settlement: Option<OraclePrice>,
+    pub bump: u8,
+}
 
-    /// Whether the market has been resolved
-    pub resolved: bool,
+impl MarketSeries {
+    /// Terms each market in the series is created with.
+    pub fn market_params(&self) -> MarketParams {
+        MarketParams {
+            collateral_per_token: self.collateral_per_token,
+            strike_exponent: self.strike_exponent,
+            confidence_mode: self.confidence_mode,
+            resolver: self.resolver,
+            creator_fee_bps: self.creator_fee_bps,
+            direction: self.direction,
+            range: None,
+            breakpoints: Vec::new(),
+            lmsr_b: 0,
+        }
+    }
+}
 
-    /// Outcome after resolution:
-    /// - None: Not resolved
-    /// - Some(1): "Yes" outcome
-    /// - Some(2): "No" outcome
-    pub outcome: Option<u8>,
+/// A recurring single-strike market. Once the current market resolves,
+/// `roll_market` lists the next one, `interval` seconds later, with its
+/// strike derived from the settlement price by `strike_rule`.
+#[account]
+#[derive(InitSpace)]
+pub struct MarketTemplate {
+    /// Creator of the template, and `authority` of every market it rolls
+    pub authority: Pubkey,
+    pub asset: u8,
+    pub collateral_mint: Pubkey,
+    pub collateral_per_token: u64,
+    pub strike_exponent: i32,
+    pub confidence_mode: ConfidenceMode,
+    pub resolver: Option<Pubkey>,
+    pub creator_fee_bps: u16,
+    pub direction: Direction,
+    pub strike_rule: StrikeRule,
+    /// Seconds between consecutive expiries
+    pub interval: i64,
+    /// Latest market in the schedule; the next roll waits for it to resolve
+    /// or void. The creator can move it with `reset_template`
+    pub current_market: Pubkey,
+    /// Markets rolled so far, not counting the first
+    pub rolls: u64,
+    pub bump: u8,
+}
+
+impl MarketTemplate {
+    /// Terms each rolled market is created with.
+    pub fn market_params(&self) -> MarketParams {
+        MarketParams {
+            collateral_per_token: self.collateral_per_token,
+            strike_exponent: self.strike_exponent,
+            confidence_mode: self.confidence_mode,
+            resolver: self.resolver,
+            creator_fee_bps: self.creator_fee_bps,
+            direction: self.direction,
+            range: None,
+            breakpoints: Vec::new(),
+            lmsr_b: 0,
+        }
+    }
+}
+
+/// How `roll_market` derives the next strike from the previous settlement price.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StrikeRule {
+    /// Spot rounded to the nearest multiple of `step` (in strike units)
+    RoundToNearest {
+        step: u64,
+    },
+    /// Spot moved by `offset_bps` (+500 is spot + 5%), rounded to the nearest
+    /// multiple of `step`
+    SpotOffset {
+        offset_bps: i16,
+        step: u64,
+    },
+}
+
+impl StrikeRule {
+    fn terms(&self) -> (i16, u64) {
+        match *self {
+            StrikeRule::RoundToNearest { step } => (0, step),
+            StrikeRule::SpotOffset { offset_bps, step } => (offset_bps, step),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let (offset_bps, step) = self.terms();
+        require!(step > 0 && (offset_bps as i64) > -(BPS_DENOMINATOR as i64), ErrorCode::InvalidStrikeRule);
+        Ok(())
+    }
+
+    /// Strike mantissa in `exponent` for a market rolled from a settlement
+    /// price of `spot`, rounding half up to the nearest `step`.
+    pub fn strike_from(&self, spot: &FixedPrice, exponent: i32) -> Result<u64> {
+        let (offset_bps, step) = self.terms();
+        let fine_exponent = exponent.min(spot.exponent);
+        let scale = 10i128.checked_pow(exponent.abs_diff(fine_exponent)).ok_or(ErrorCode::Overflow)?;
+        let step = (step as i128).checked_mul(scale).ok_or(ErrorCode::Overflow)?;
+        let target = spot
+            .rescale(fine_exponent)?
+            .checked_mul((BPS_DENOMINATOR as i128) + (offset_bps as i128))
+            .ok_or(ErrorCode::Overflow)? / (BPS_DENOMINATOR as i128);
+        let strike = (target.checked_add(step / 2).ok_or(ErrorCode::Overflow)? / step) * step / scale;
+        require!(strike > 0, ErrorCode::InvalidStrikeRule);
+        Ok(u64::try_from(strike).map_err(|_| ErrorCode::Overflow)?)
+    }
 }
 
+/// Most outcome buckets a bucket market can have.
+pub const MAX_BUCKETS: usize = 8;
+pub const MAX_BREAKPOINTS: usize = MAX_BUCKETS - 1;
+
 impl Market {
-    /// Byte-length of the Market struct (excluding discriminator).
-    /// Helps when we declare `space` in #[account(init, space = ...)]
-    pub const LEN: usize =
-        // authority
-        32 +
-        // strike
-        8 +
-        // expiry
-        8 +
-        // asset
-        1 +
-        // resolved
-        1 +
-        // outcome: Option<u8> => 1 byte
-        2;
+    /// Size (excluding discriminator) of markets created before `version`
+    /// existed, see `LegacyMarket`.
+    pub const LEGACY_LEN: usize = LegacyMarket::INIT_SPACE;
+
+    /// Strike as a fixed-point price, comparable against oracle prices.
+    pub fn strike_price(&self) -> FixedPrice {
+        FixedPrice::new(self.strike as i128, self.strike_exponent)
+    }
+
+    /// Owned copy of the market PDA seeds with `bump`, for signing CPIs.
+    pub fn signer_seeds(&self, bump: u8) -> MarketSignerSeeds {
+        MarketSignerSeeds::new(self.authority, self.strike, self.expiry, self.direction, self.terms_seed(), bump)
+    }
+
+    /// Range or bucket suffix of the market PDA seeds, see `terms_seed_for`.
+    pub fn terms_seed(&self) -> Vec<u8> {
+        Market::terms_seed_for(&self.range, self.bucket_breakpoints())
+    }
+
+    /// Extra market PDA seed distinguishing range and bucket markets from
+    /// single-strike ones. Bucket markets hash their breakpoints, which can
+    /// be longer than the 32 bytes a single seed allows.
+    pub fn terms_seed_for(range: &Option<StrikeRange>, breakpoints: &[u64]) -> Vec<u8> {
+        if breakpoints.is_empty() {
+            return StrikeRange::seed_for(range);
+        }
+        let bytes: Vec<u8> = breakpoints.iter().flat_map(|breakpoint| breakpoint.to_le_bytes()).collect();
+        hashv(&[b"buckets", &bytes]).to_bytes().to_vec()
+    }
+
+    /// Breakpoints in use, empty for YES/NO markets.
+    pub fn bucket_breakpoints(&self) -> &[u64] {
+        &self.breakpoints[..(self.bucket_count as usize).saturating_sub(1)]
+    }
+
+    /// Bucket a price settles in: the number of breakpoints at or below it.
+    pub fn bucket_for(&self, price: &FixedPrice) -> Result<u8> {
+        let mut bucket = 0;
+        for breakpoint in self.bucket_breakpoints() {
+            let breakpoint = FixedPrice::new(*breakpoint as i128, self.strike_exponent);
+            if price.checked_cmp(&breakpoint)? == Ordering::Less {
+                break;
+            }
+            bucket += 1;
+        }
+        Ok(bucket)
+    }
+
+    /// Interval notation for a bucket's token name, e.g. `<150`, `[150-170)`, `>=170`.
+    pub fn bucket_label(&self, index: u8) -> String {
+        let breakpoints = self.bucket_breakpoints();
+        let index = index as usize;
+        if index == 0 {
+            format!("<{}", breakpoints[0])
+        } else if index == breakpoints.len() {
+            format!(">={}", breakpoints[index - 1])
+        } else {
+            format!("[{}-{})", breakpoints[index - 1], breakpoints[index])
+        }
+    }
+
+    /// Collateral the vault owes outcome holders once the market is settled.
+    pub fn settled_liabilities(&self, yes_supply: u64, no_supply: u64) -> Result<u64> {
+        let tokens = match self.outcome {
+            Some(Outcome::Yes) => yes_supply as u128,
+            Some(Outcome::No) => no_supply as u128,
+            Some(Outcome::Void) => ((yes_supply as u128) + (no_supply as u128)).div_ceil(2),
+            None => {
+                return Err(ErrorCode::MarketNotResolved.into());
+            }
+        };
+        let collateral = tokens
+            .checked_mul(self.collateral_per_token as u128)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(u64::try_from(collateral).map_err(|_| ErrorCode::Overflow)?)
+    }
+
+    /// Moves an `Open` market to `Expired` once `now` has reached expiry.
+    /// Expiry is time-driven, so handlers call this before checking status.
+    pub fn refresh_status(&mut self, now: i64) {
+        if self.status == MarketStatus::Open && now >= self.expiry {
+            self.status = MarketStatus::Expired;
+        }
+    }
+
+    /// Fails unless the market is in one of `allowed`. Halted and closed
+    /// markets report `MarketHalted`/`MarketClosed`, anything else `error`.
+    pub fn require_status(&self, allowed: &[MarketStatus], error: ErrorCode) -> Result<()> {
+        if allowed.contains(&self.status) {
+            return Ok(());
+        }
+        msg!("Market is {:?}, expected one of {:?}", self.status, allowed);
+        match self.status {
+            MarketStatus::Halted => Err(ErrorCode::MarketHalted.into()),
+            MarketStatus::Closed => Err(ErrorCode::MarketClosed.into()),
+            _ => Err(error.into()),
+        }
+    }
+}
+
+/// Which side of the strike a market pays YES on. Legacy markets are
+/// `AboveOrEqual`, whose zero byte is also what `migrate_market` leaves.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Direction {
+    /// YES if price >= strike
+    #[default]
+    AboveOrEqual,
+    /// YES if price > strike
+    Above,
+    /// YES if price < strike
+    Below,
+    /// YES if price <= strike
+    BelowOrEqual,
+}
+
+impl Direction {
+    /// Extra market PDA seed. Empty for `AboveOrEqual` so markets created
+    /// before directions existed keep their addresses.
+    pub fn seed(&self) -> &'static [u8] {
+        match self {
+            Direction::AboveOrEqual => b"",
+            Direction::Above => b"gt",
+            Direction::Below => b"lt",
+            Direction::BelowOrEqual => b"le",
+        }
+    }
+
+    /// Short form used in outcome token names and logs.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Direction::AboveOrEqual => "GTE",
+            Direction::Above => "GT",
+            Direction::Below => "LT",
+            Direction::BelowOrEqual => "LTE",
+        }
+    }
+
+    /// Whether a settlement price comparing to the strike as `ordering` is YES.
+    pub fn is_yes(&self, ordering: Ordering) -> bool {
+        match self {
+            Direction::AboveOrEqual => ordering != Ordering::Less,
+            Direction::Above => ordering == Ordering::Greater,
+            Direction::Below => ordering == Ordering::Less,
+            Direction::BelowOrEqual => ordering != Ordering::Greater,
+        }
+    }
+}
+
+/// Upper bound and bound inclusivity of a range market.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StrikeRange {
+    /// Upper strike, in the same exponent as `Market.strike`
+    pub upper_strike: u64,
+    pub lower_inclusive: bool,
+    pub upper_inclusive: bool,
+}
+
+impl StrikeRange {
+    /// Extra market PDA seed. Empty for single-strike markets so their
+    /// addresses are unchanged; otherwise tagged so no range market can
+    /// collide with a single-strike one.
+    pub fn seed_for(range: &Option<StrikeRange>) -> Vec<u8> {
+        match range {
+            None => Vec::new(),
+            Some(range) => {
+                let mut seed = b"range".to_vec();
+                seed.extend_from_slice(&range.upper_strike.to_le_bytes());
+                seed.push(((range.lower_inclusive as u8) << 1) | (range.upper_inclusive as u8));
+                seed
+            }
+        }
+    }
+
+    /// Whether a price comparing to the lower and upper strikes as given is inside.
+    pub fn contains(&self, to_lower: Ordering, to_upper: Ordering) -> bool {
+        let above_lower = match to_lower {
+            Ordering::Greater => true,
+            Ordering::Equal => self.lower_inclusive,
+            Ordering::Less => false,
+        };
+        let below_upper = match to_upper {
+            Ordering::Less => true,
+            Ordering::Equal => self.upper_inclusive,
+            Ordering::Greater => false,
+        };
+        above_lower && below_upper
+    }
+
+    /// Interval notation for token names, e.g. `[150-170)`.
+    pub fn label(&self, lower_strike: u64) -> String {
+        format!(
+            "{}{}-{}{}",
+            if self.lower_inclusive { "[" } else { "(" },
+            lower_strike,
+            self.upper_strike,
+            if self.upper_inclusive { "]" } else { ")" }
+        )
+    }
+}
+
+/// One of a market's two outcome tokens.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Yes,
+    No,
+}
+
+/// Lifecycle of a `Market`:
+/// `Created -> MintsReady -> Open -> Expired -> Resolved | Voided -> Closed`,
+/// with `Open <-> Halted` by the admin. `cancel_market` voids markets before
+/// any outcome tokens exist; later voids go through `force_resolve`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarketStatus {
+    /// Market account exists, outcome mints not yet created
+    Created,
+    /// Outcome mints exist, waiting for the creator to open trading
+    MintsReady,
+    /// Complete sets can be minted and burned
+    Open,
+    /// Stopped by the admin; nothing but admin actions until resumed
+    Halted,
+    /// Past expiry, waiting for resolution
+    Expired,
+    /// Settled YES or NO
+    Resolved,
+    /// Cancelled or settled void; YES and NO each redeem for half a unit
+    Voided,
+    /// All outcome tokens redeemed and the vault closed
+    Closed,
+}
+
+/// Result of a resolved market.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// On bucket markets, `Market.winning_bucket` pays and every other bucket is worthless
+    Yes,
+    No,
+    /// YES and NO each redeem for half a collateral unit (each bucket for
+    /// `1 / bucket_count` on bucket markets)
+    Void,
+}
+
+/// Initial `ProgramConfig.resolver_grace_period`.
+pub const DEFAULT_RESOLVER_GRACE_PERIOD: i64 = 60 * 60;
+
+/// Initial `ProgramConfig.force_resolve_timeout`.
+pub const DEFAULT_FORCE_RESOLVE_TIMEOUT: i64 = 7 * 24 * 60 * 60;
+
+/// Singleton holding the program admin and global settings.
+#[account]
+#[derive(InitSpace)]
+pub struct ProgramConfig {
+    /// Key allowed to run admin instructions
+    pub admin: Pubkey,
+
+    /// Key proposed by the admin; becomes admin once it calls `accept_admin`
+    pub pending_admin: Option<Pubkey>,
+
+    /// Account protocol fees are paid to
+    pub fee_recipient: Pubkey,
+
+    /// Bitmask of paused operations, zero when nothing is paused
+    pub pause_flags: u8,
+
+    /// Seconds after expiry during which only `Market.resolver` may resolve
+    pub resolver_grace_period: i64,
+
+    /// Seconds after expiry after which the admin may `force_resolve` a
+    /// market the oracle never produced a valid settlement price for
+    pub force_resolve_timeout: i64,
+
+    pub bump: u8,
+
+    /// Key that may pause (but not unpause) the program or a market
+    pub guardian: Option<Pubkey>,
+
+    /// Protocol fee on collateral locked by `mint_complete_set`, in bps
+    pub deposit_fee_bps: u16,
+
+    /// Protocol fee on winning `redeem` payouts, in bps
+    pub redeem_fee_bps: u16,
+
+    /// Highest `creator_fee_bps` a new market may set
+    pub max_creator_fee_bps: u16,
+
+    /// Zeroed space for future fields
+    pub reserved: [u8; CONFIG_RESERVED_BYTES],
+}
+
+/// Bytes of `ProgramConfig.reserved` left for future fields.
+pub const CONFIG_RESERVED_BYTES: usize = 89;
+
+/// Upper bound on each protocol fee, in bps.
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+
+/// Basis-point denominator.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// `pause_flags` bit stopping `mint_complete_set` and `burn_complete_set`.
+pub const PAUSE_MINT: u8 = 1 << 0;
+/// `pause_flags` bit stopping trading of outcome tokens.
+pub const PAUSE_TRADE: u8 = 1 << 1;
+/// `pause_flags` bit stopping `resolve_market`.
+pub const PAUSE_RESOLVE: u8 = 1 << 2;
+/// `pause_flags` bit stopping `redeem`.
+pub const PAUSE_REDEEM: u8 = 1 << 3;
+/// Every defined `PAUSE_*` bit.
+pub const PAUSE_ALL: u8 = PAUSE_MINT | PAUSE_TRADE | PAUSE_RESOLVE | PAUSE_REDEEM;
+
+impl ProgramConfig {
+    /// Fails if `operation` (a `PAUSE_*` bit) is paused globally or on `market`.
+    pub fn require_not_paused(&self, market: &Market, operation: u8) -> Result<()> {
+        if (self.pause_flags | market.pause_flags) & operation != 0 {
+            msg!("⚠️ Operation {:#04b} is paused", operation);
+            return Err(ErrorCode::OperationPaused.into());
+        }
+        Ok(())
+    }
+
+    /// Whether `key` may pause: the admin or the guardian.
+    pub fn can_pause(&self, key: &Pubkey) -> bool {
+        self.admin == *key || self.guardian == Some(*key)
+    }
+}
+
+/// Admin-supplied settings for `update_config`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigParams {
+    pub fee_recipient: Pubkey,
+    pub guardian: Option<Pubkey>,
+    pub deposit_fee_bps: u16,
+    pub redeem_fee_bps: u16,
+    pub max_creator_fee_bps: u16,
+    pub pause_flags: u8,
+    pub resolver_grace_period: i64,
+    pub force_resolve_timeout: i64,
+}
+
+/// Creator-chosen settings for a new market, beside the seed arguments.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MarketParams {
+    /// Collateral base units locked per complete set
+    pub collateral_per_token: u64,
+    /// Decimal exponent of the strike
+    pub strike_exponent: i32,
+    /// Must be `Reject` for bucket markets
+    pub confidence_mode: ConfidenceMode,
+    pub resolver: Option<Pubkey>,
+    /// Fee paid to the creator on collateral locked, capped by the config
+    pub creator_fee_bps: u16,
+    /// Which side of the strike settles YES; part of the market address
+    pub direction: Direction,
+    /// Makes this a range market with `strike` as the lower bound; part of
+    /// the market address. Requires the default direction.
+    pub range: Option<StrikeRange>,
+    /// Ascending price breakpoints making this a bucket market with one
+    /// outcome mint per bucket; part of the market address. Empty for YES/NO
+    /// markets, otherwise the first must equal `strike`.
+    pub breakpoints: Vec<u64>,
+    /// LMSR liquidity parameter; non-zero funds an LMSR market maker from
+    /// the creator's collateral account
+    pub lmsr_b: u64,
+}
+
+/// How a market reached its outcome.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolutionPath {
+    /// Oracle settlement by the designated resolver
+    Resolver,
+    /// Oracle settlement by any cranker
+    Permissionless,
+    /// Admin-supplied outcome after the oracle timeout
+    ForceResolve,
+    /// Admin cancellation, market is void
+    Cancelled,
+}
+
+#[event]
+pub struct MarketResolved {
+    pub market: Pubkey,
+    pub outcome: Outcome,
+    pub price: FixedPrice,
+    pub publish_time: i64,
+    pub path: ResolutionPath,
+    pub resolved_by: Pubkey,
+    /// Winning bucket when a bucket market resolves `Yes`
+    pub winning_bucket: Option<u8>,
+}
+
+/// Behaviour of `resolve_market` when `|price - strike| < k * conf`, i.e. the
+/// oracle cannot tell which side of the strike the asset settled on.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfidenceMode {
+    /// Reject the resolution; a later price update has to be used. If every
+    /// update in the settlement window straddles the strike, the market can
+    /// only be settled through `force_resolve` after the timeout.
+    Reject,
+    /// Settle YES only if the whole `k * conf` band clears the strike, NO otherwise
+    BandMustClear,
+}
+
+impl ConfidenceMode {
+    /// Whether a market settles YES given the plain comparison `yes` and
+    /// whether the `k * conf` band straddled a strike. `None` means the
+    /// resolution must be retried with a later update.
+    pub fn guard(&self, yes: bool, near_strike: bool) -> Option<bool> {
+        match (self, near_strike) {
+            (_, false) => Some(yes),
+            (ConfidenceMode::Reject, true) => None,
+            (ConfidenceMode::BandMustClear, true) => Some(false),
+        }
+    }
+}
+
+/// Admin-supplied settings for an `AssetConfig`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AssetConfigParams {
+    pub feed_id: [u8; 32],
+    pub symbol: String,
+    pub price_exponent: i32,
+    pub max_staleness: u64,
+    pub max_confidence_bps: u16,
+    pub strike_confidence_k_bps: u32,
+    pub settlement_window: u64,
+}
+
+/// A validated oracle reading: price and confidence share `price.exponent`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug)]
+pub struct OraclePrice {
+    pub price: FixedPrice,
+    pub conf: u64,
+    pub publish_time: i64,
+}
+
+/// Longest display symbol an `AssetConfig` may carry (e.g. "BONK", "EURUSD").
+pub const MAX_ASSET_SYMBOL_LEN: usize = 10;
+
+/// Admin-managed registry entry describing one underlying and its Pyth feed.
+/// Seeded by the asset id stored in `Market.asset`, so new underlyings can be
+/// listed without a program upgrade.
+#[account]
+#[derive(InitSpace)]
+pub struct AssetConfig {
+    /// Id markets reference through `Market.asset`
+    pub asset: u8,
+
+    /// Pyth price feed id (see https://pyth.network/developers/price-feed-ids)
+    pub feed_id: [u8; 32],
+
+    /// Display symbol used in outcome token names
+    #[max_len(MAX_ASSET_SYMBOL_LEN)]
+    pub symbol: String,
+
+    /// Exponent the feed publishes with; updates with any other exponent are rejected
+    pub price_exponent: i32,
+
+    /// Maximum age in seconds of a price update accepted for this asset
+    pub max_staleness: u64,
+
+    /// Maximum confidence interval as basis points of the price
+    pub max_confidence_bps: u16,
+
+    /// Multiplier `k` (in bps, 10_000 = 1x) applied to the confidence interval
+    /// when checking whether the price is too close to the strike to settle.
+    /// Zero disables the check.
+    pub strike_confidence_k_bps: u32,
+
+    /// Seconds after a market's expiry during which a published price may
+    /// settle it, at most `MAX_SETTLEMENT_WINDOW`. Any update inside the
+    /// window can be picked by whoever cranks resolution, so it stays short.
+    pub settlement_window: u64,
+
+    /// Whether new markets may be created on this asset
+    pub enabled: bool,
+
+    pub bump: u8,
+}
+
+/// Longest `AssetConfig.settlement_window`, in seconds. Pyth publishes
+/// several updates a second, so this still leaves room for a late crank.
+pub const MAX_SETTLEMENT_WINDOW: u64 = 10;
+
+/// Smallest and largest decimal exponent accepted for strikes and oracle prices.
+pub const MIN_PRICE_EXPONENT: i32 = -18;
+pub const MAX_PRICE_EXPONENT: i32 = 18;
+
+/// Exact fixed-point price: `mantissa * 10^exponent`, the same shape Pyth
+/// publishes. All comparisons happen in integer space, so settlement never
+/// depends on float rounding.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedPrice {
+    pub mantissa: i128,
+    pub exponent: i32,
+}
+
+impl FixedPrice {
+    pub fn new(mantissa: i128, exponent: i32) -> Self {
+        Self { mantissa, exponent }
+    }
+
+    /// Mantissa expressed with `exponent`. Scaling to a smaller exponent is
+    /// overflow-checked; scaling to a larger one only succeeds if no digits
+    /// are dropped.
+    pub fn rescale(&self, exponent: i32) -> Result<i128> {
+        require!(
+            (MIN_PRICE_EXPONENT..=MAX_PRICE_EXPONENT).contains(&exponent) &&
+                (MIN_PRICE_EXPONENT..=MAX_PRICE_EXPONENT).contains(&self.exponent),
+            ErrorCode::InvalidPriceExponent
+        );
+        let shift = self.exponent.abs_diff(exponent);
+        let factor = 10i128.checked_pow(shift).ok_or(ErrorCode::Overflow)?;
+        if exponent <= self.exponent {
+            Ok(self.mantissa.checked_mul(factor).ok_or(ErrorCode::Overflow)?)
+        } else {
+            require!(self.mantissa % factor == 0, ErrorCode::PricePrecisionLoss);
+            Ok(self.mantissa / factor)
+        }
+    }
+
+    /// Exact ordering of two prices, rescaling both to the finer exponent.
+    pub fn checked_cmp(&self, other: &FixedPrice) -> Result<Ordering> {
+        let exponent = self.exponent.min(other.exponent);
+        Ok(self.rescale(exponent)?.cmp(&other.rescale(exponent)?))
+    }
 }
 
 #[derive(Accounts)]
@@ -71,6 +1021,13 @@ pub struct ResolveMarket<'info> {
     pub signer: Signer<'info>,
     #[account(mut)]
     pub market: Account<'info, Market>,
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        seeds = [b"asset_config".as_ref(), &[market.asset]],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
     /// CHECK: The Pyth price account
     pub price_account: Account<'info, PriceUpdateV2>,
 }
@@ -82,405 +1039,1975 @@ pub struct GetPriceFeed<'info> {
     pub price_update: Account<'info, PriceUpdateV2>,
 }
 
-#[derive(Accounts)]
-pub struct FetchCoinPrice<'info> {
-    #[account(mut)]
-    pub signer: Signer<'info>,
-    ///CHECK = The Pyth price account
-    pub price_update: Account<'info, PriceUpdateV2>,
+#[derive(Accounts)]
+pub struct FetchAssetPrice<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub asset_config: Account<'info, AssetConfig>,
+    ///CHECK = The Pyth price account
+    pub price_update: Account<'info, PriceUpdateV2>,
+}
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Box<Account<'info, Market>>, // ✅ Market PDA owns the collateral vault
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Omitted when the user holds no YES
+    #[account(
+        mut,
+        associated_token::mint = yes_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_yes_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Omitted when the user holds no NO
+    #[account(
+        mut,
+        associated_token::mint = no_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_no_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = user,
+        token::token_program = collateral_token_program,
+    )]
+    pub user_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref(), collateral_mint.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Deposits collateral into the market vault and mints a complete set
+/// (one YES and one NO per unit) to the user.
+#[derive(Accounts)]
+pub struct MintCompleteSet<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+        mint::authority = market,
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+        mint::authority = market,
+    )]
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = yes_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_yes_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = no_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_no_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = user,
+        token::token_program = collateral_token_program,
+    )]
+    pub user_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref(), collateral_mint.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// Burns a complete set (one YES and one NO per unit) from the user and
+/// returns the locked collateral. Only possible before resolution.
+#[derive(Accounts)]
+pub struct BurnCompleteSet<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = yes_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_yes_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = no_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_no_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = user,
+        token::token_program = collateral_token_program,
+    )]
+    pub user_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+//ADMIN STUFF
+
+/// Admin action on a single market (`force_resolve`, `halt_market`,
+/// `resume_market`).
+#[derive(Accounts)]
+pub struct AdminMarket<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+}
+
+/// Admin cancellation of a market nobody holds positions in. The outcome
+/// mints are only needed to prove that for an `Open` market.
+#[derive(Accounts)]
+pub struct CancelMarket<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(seeds = [b"yes_mint", market.key().as_ref()], bump)]
+    pub yes_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    #[account(seeds = [b"no_mint", market.key().as_ref()], bump)]
+    pub no_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64, expiry: i64, asset: u8, params: MarketParams)]
+pub struct InitializeMarket<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [
+            b"market".as_ref(),
+            authority.key().as_ref(),
+            &strike.to_le_bytes(),
+            &expiry.to_le_bytes(),
+            params.direction.seed(),
+            &Market::terms_seed_for(&params.range, &params.breakpoints),
+        ],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"asset_config".as_ref(), &[asset]],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(mint::token_program = collateral_token_program)]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Funds the LMSR subsidy; only needed when `params.lmsr_b` is non-zero
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = authority,
+        token::token_program = collateral_token_program,
+    )]
+    pub authority_collateral_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Market-owned ATA holding all collateral locked in complete sets
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury".as_ref(), collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mint::token_program = collateral_token_program)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub treasury_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = config.admin == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// Creates the YES/NO outcome mints as Token-2022 mints carrying their own
+/// metadata (metadata pointer + token metadata extensions).
+#[derive(Accounts)]
+pub struct CreateMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"market".as_ref(), market.authority.key().as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"asset_config".as_ref(), &[market.asset]],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    // The market PDA is the mint authority so it can mint complete sets
+    #[account(
+        init,
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = market,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = market,
+        extensions::metadata_pointer::metadata_address = yes_mint,
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = market,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = market,
+        extensions::metadata_pointer::metadata_address = no_mint,
+    )]
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct CreateBucketMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"asset_config".as_ref(), &[market.asset]],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    // One mint per bucket, indexed from the lowest price bucket
+    #[account(
+        init,
+        seeds = [b"bucket_mint", market.key().as_ref(), &[index]],
+        bump,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = market,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = market,
+        extensions::metadata_pointer::metadata_address = bucket_mint,
+    )]
+    pub bucket_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Bucket mints and the user's token accounts for them are passed as
+/// remaining accounts, see `mint_bucket_set`.
+#[derive(Accounts)]
+pub struct MintBucketSet<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = user,
+        token::token_program = collateral_token_program,
+    )]
+    pub user_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref(), collateral_mint.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct RedeemBucket<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"bucket_mint", market.key().as_ref(), &[index]],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub bucket_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = bucket_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_bucket_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = user,
+        token::token_program = collateral_token_program,
+    )]
+    pub user_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref(), collateral_mint.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(expiry: i64, asset: u8)]
+pub struct InitializeSeries<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MarketSeries::INIT_SPACE,
+        seeds = [b"series".as_ref(), authority.key().as_ref(), &[asset], &expiry.to_le_bytes()],
+        bump
+    )]
+    pub series: Account<'info, MarketSeries>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"asset_config".as_ref(), &[asset]],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(mint::token_program = collateral_token_program)]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub system_program: Program<'info, System>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+/// For each strike, the market, its collateral vault and its YES and NO
+/// mints follow as remaining accounts, in that order.
+#[derive(Accounts)]
+pub struct CreateSeries<'info> {
+    #[account(
+        mut,
+        seeds = [b"series".as_ref(), series.authority.as_ref(), &[series.asset], &series.expiry.to_le_bytes()],
+        bump = series.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+        has_one = collateral_mint @ ErrorCode::InvalidTokenMint
+    )]
+    pub series: Box<Account<'info, MarketSeries>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"asset_config".as_ref(), &[series.asset]],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    #[account(mint::token_program = collateral_token_program)]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token2022>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// The series' markets to settle follow as writable remaining accounts.
+#[derive(Accounts)]
+pub struct ResolveSeries<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"series".as_ref(), series.authority.as_ref(), &[series.asset], &series.expiry.to_le_bytes()],
+        bump = series.bump
+    )]
+    pub series: Box<Account<'info, MarketSeries>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        seeds = [b"asset_config".as_ref(), &[series.asset]],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    /// Only read until the series has recorded its settlement observation
+    pub price_account: Option<Account<'info, PriceUpdateV2>>,
+}
+
+#[derive(Accounts)]
+#[instruction(interval: i64)]
+pub struct InitializeTemplate<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MarketTemplate::INIT_SPACE,
+        seeds = [b"template".as_ref(), authority.key().as_ref(), &[first_market.asset], &interval.to_le_bytes()],
+        bump
+    )]
+    pub template: Account<'info, MarketTemplate>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// First market of the schedule, whose terms every rolled market copies
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub first_market: Box<Account<'info, Market>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// The next market, its collateral vault and its YES and NO mints follow as
+/// remaining accounts, as for `create_series`.
+#[derive(Accounts)]
+pub struct RollMarket<'info> {
+    /// Pays rent for the four new accounts and is not reimbursed: the vault
+    /// rent `close_market` frees goes to `template.authority`
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"template".as_ref(), template.authority.as_ref(), &[template.asset], &template.interval.to_le_bytes()],
+        bump = template.bump,
+        has_one = collateral_mint @ ErrorCode::InvalidTokenMint
+    )]
+    pub template: Box<Account<'info, MarketTemplate>>,
+
+    #[account(address = template.current_market @ ErrorCode::InvalidMarketAccount)]
+    pub previous_market: Box<Account<'info, Market>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        seeds = [b"asset_config".as_ref(), &[template.asset]],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    #[account(mint::token_program = collateral_token_program)]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token2022>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(asset: u8)]
+pub struct AddAssetConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AssetConfig::INIT_SPACE,
+        seeds = [b"asset_config".as_ref(), &[asset]],
+        bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAssetConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"asset_config".as_ref(), &[asset_config.asset]],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+/// Template creator moving `current_market` (`reset_template`).
+#[derive(Accounts)]
+pub struct ResetTemplate<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"template".as_ref(), template.authority.as_ref(), &[template.asset], &template.interval.to_le_bytes()],
+        bump = template.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub template: Box<Account<'info, MarketTemplate>>,
+
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub market: Box<Account<'info, Market>>,
+}
+
+/// Creator action on their own market (`open_market`).
+#[derive(Accounts)]
+pub struct MarketAuthority<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub market: Account<'info, Market>,
+}
+
+/// Marks a fully redeemed market `Closed`, sweeping rounding dust left in the
+/// collateral vault to the creator and closing the vault. Bucket markets have
+/// no YES/NO mints and pass their bucket mints as remaining accounts.
+#[derive(Accounts)]
+pub struct CloseMarket<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump
+    )]
+    pub yes_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump
+    )]
+    pub no_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::token_program = collateral_token_program,
+    )]
+    pub authority_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+// Market accounts never change size: a new field that does not shrink
+// `reserved` by its own size fails to compile here.
+const _: () = assert!(Market::INIT_SPACE == MARKET_ACCOUNT_SPACE);
+
+/// Market PDA seeds with the bump, owned so they outlive the borrow of the
+/// market. `seeds()` gives the slices `invoke_signed` expects.
+pub struct MarketSignerSeeds {
+    authority: Pubkey,
+    strike: [u8; 8],
+    expiry: [u8; 8],
+    direction: &'static [u8],
+    terms: Vec<u8>,
+    bump: [u8; 1],
+}
+
+impl MarketSignerSeeds {
+    pub fn new(authority: Pubkey, strike: u64, expiry: i64, direction: Direction, terms: Vec<u8>, bump: u8) -> Self {
+        MarketSignerSeeds {
+            authority,
+            strike: strike.to_le_bytes(),
+            expiry: expiry.to_le_bytes(),
+            direction: direction.seed(),
+            terms,
+            bump: [bump],
+        }
+    }
+
+    pub fn seeds(&self) -> [&[u8]; 7] {
+        [b"market", self.authority.as_ref(), &self.strike, &self.expiry, self.direction, &self.terms, &self.bump]
+    }
+}
+
+/// Layout of markets created before `version` existed. Only read by
+/// `migrate_market`, which rewrites them into the current layout.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct LegacyMarket {
+    pub authority: Pubkey,
+    pub strike: u64,
+    pub expiry: i64,
+    pub asset: u8,
+    pub resolved: bool,
+    /// `Some(1)` YES, `Some(2)` NO
+    pub outcome: Option<u8>,
+}
+
+/// Grows an unversioned `Market` to the current layout. Permissionless; the
+/// payer funds the extra rent.
+#[derive(Accounts)]
+pub struct MigrateMarket<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: may still be in the legacy layout, so it cannot be deserialized
+    /// as `Market`; owner and discriminator are checked in `migrate_market`.
+    #[account(mut, owner = crate::ID)]
+    pub market: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates the `ProgramConfig` singleton. Only the program's upgrade
+/// authority may do this; it becomes the first admin.
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProgramConfig::INIT_SPACE,
+        seeds = [b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::BinaryOptions>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Admin change to the `ProgramConfig` (`propose_admin`, `update_config`).
+#[derive(Accounts)]
+pub struct ConfigAdmin<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+/// Second step of an admin transfer, signed by the proposed admin.
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub new_admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = config.pending_admin == Some(new_admin.key()) @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+/// Pause by the admin or guardian (`pause`, `pause_market`).
+#[derive(Accounts)]
+pub struct PauseAuthority<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = config.can_pause(&authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+/// Pause of a single market by the admin or guardian.
+#[derive(Accounts)]
+pub struct PauseMarket<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = config.can_pause(&authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+}
+
+/// Pays collected protocol fees out to the config's `fee_recipient`.
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref(), collateral_mint.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mint::token_program = collateral_token_program)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub treasury_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = config.fee_recipient,
+        token::token_program = collateral_token_program,
+    )]
+    pub recipient_collateral_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Pays a market's accrued creator fees to its authority.
+#[derive(Accounts)]
+pub struct ClaimCreatorFees<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::token_program = collateral_token_program,
+    )]
+    pub authority_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Creates a market's YES/NO pool, its LP mint and reserve accounts.
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool", market.key().as_ref()],
+        bump
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"lp_mint", pool.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = pool,
+        mint::token_program = token_program,
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = yes_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_yes_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = no_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_no_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// Deposit into or withdrawal from a pool (`add_liquidity`, `remove_liquidity`).
+#[derive(Accounts)]
+pub struct PoolLiquidity<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", market.key().as_ref()],
+        bump = pool.bump,
+        has_one = market,
+        has_one = lp_mint,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, mint::token_program = token_program)]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = yes_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_yes_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = no_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_no_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = yes_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_yes_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = no_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_no_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = lp_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_lp_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Holds the `MINIMUM_LIQUIDITY` locked on the first deposit
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = lp_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// Burns a settled market's remaining pool reserves (`redeem_pool_reserves`).
+#[derive(Accounts)]
+pub struct PoolReserves<'info> {
+    #[account(
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
+        bump
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", market.key().as_ref()],
+        bump = pool.bump,
+        has_one = market,
+        has_one = lp_mint,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mint::token_program = token_program)]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"no_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+    )]
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = yes_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_yes_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = no_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_no_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Holds the `MINIMUM_LIQUIDITY` locked on the first deposit
+    #[account(
+        associated_token::mint = lp_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
+
+/// YES for NO (or NO for YES) against a market's pool.
 #[derive(Accounts)]
-pub struct Redeem<'info> {
+pub struct PoolSwap<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes()],
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
         bump
     )]
-    pub market: Box<Account<'info, Market>>, // ✅ Market PDA holds the locked lamports
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
 
     #[account(
         mut,
+        seeds = [b"pool", market.key().as_ref()],
+        bump = pool.bump,
+        has_one = market,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
         seeds = [b"yes_mint", market.key().as_ref()],
-        bump
+        bump,
+        mint::token_program = token_program,
     )]
-    pub yes_mint: Box<Account<'info, Mint>>,
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        mut,
         seeds = [b"no_mint", market.key().as_ref()],
-        bump
+        bump,
+        mint::token_program = token_program,
     )]
-    pub no_mint: Box<Account<'info, Mint>>,
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = yes_mint,
-        associated_token::authority = market,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
     )]
-    pub treasury_yes_token_account: Box<Account<'info, TokenAccount>>,
+    pub pool_yes_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         associated_token::mint = no_mint,
-        associated_token::authority = market,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
     )]
-    pub treasury_no_token_account: Box<Account<'info, TokenAccount>>,
+    pub pool_no_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = yes_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_program,
     )]
-    pub user_yes_token_account: Account<'info, TokenAccount>,
+    pub user_yes_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = no_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_program,
     )]
-    pub user_no_token_account: Account<'info, TokenAccount>,
+    pub user_no_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
+/// Buy from or sell to a market's LMSR market maker (`lmsr_buy`, `lmsr_sell`).
 #[derive(Accounts)]
-pub struct LockFunds<'info> {
+pub struct LmsrTrade<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes()],
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
         bump
     )]
     pub market: Box<Account<'info, Market>>,
 
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
     #[account(
         mut,
         seeds = [b"yes_mint", market.key().as_ref()],
-        bump
+        bump,
+        mint::token_program = token_program,
+        mint::authority = market,
     )]
-    pub yes_mint: Box<Account<'info, Mint>>,
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         seeds = [b"no_mint", market.key().as_ref()],
-        bump
+        bump,
+        mint::token_program = token_program,
+        mint::authority = market,
     )]
-    pub no_mint: Box<Account<'info, Mint>>,
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = yes_mint,
-        associated_token::authority = market,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
     )]
-    pub treasury_yes_token_account: Box<Account<'info, TokenAccount>>,
+    pub user_yes_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = no_mint,
-        associated_token::authority = market,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
     )]
-    pub treasury_no_token_account: Box<Account<'info, TokenAccount>>,
+    pub user_no_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        init_if_needed,
-        payer = user,
-        associated_token::mint = yes_mint,
-        associated_token::authority = user
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
     )]
-    pub user_yes_token_account: Account<'info, TokenAccount>,
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        init_if_needed,
-        payer = user,
-        associated_token::mint = no_mint,
-        associated_token::authority = user
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = user,
+        token::token_program = collateral_token_program,
     )]
-    pub user_no_token_account: Account<'info, TokenAccount>,
+    pub user_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
-//ADMIN STUFF
-
+/// Returns the unused LMSR subsidy to the creator of a settled market.
 #[derive(Accounts)]
-#[instruction(strike: u64, expiry: i64, asset: u8)]
-pub struct InitializeMarket<'info> {
+pub struct ReclaimLmsrSubsidy<'info> {
+    pub authority: Signer<'info>,
+
     #[account(
-        init,
-        payer = authority,
-        space = 8 + Market::LEN,
-        seeds = [
-            b"market".as_ref(),
-            authority.key().as_ref(),
-            &strike.to_le_bytes(),
-            &expiry.to_le_bytes(),
-        ],
+        has_one = authority @ ErrorCode::Unauthorized,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
         bump
     )]
-    pub market: Account<'info, Market>,
-
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    pub market: Box<Account<'info, Market>>,
 
-    pub system_program: Program<'info, System>,
-}
+    #[account(
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
 
-#[derive(Accounts)]
-pub struct InitializeTreasury<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8,
-        seeds = [b"treasury".as_ref(), authority.key().as_ref()],
+        seeds = [b"no_mint", market.key().as_ref()],
         bump
     )]
-    ///CHECK: The treasury account
-    pub treasury: AccountInfo<'info>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(address = system_program::ID)]
-    pub system_program: Program<'info, System>,
-}
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
 
-#[derive(Accounts)]
-pub struct InitializeOutcomeMints<'info> {
     #[account(
         mut,
-        seeds = [b"market".as_ref(), market.authority.key().as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes()],
-        bump
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
     )]
-    pub market: Account<'info, Market>,
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::token_program = collateral_token_program,
+    )]
+    pub authority_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Creates a market's order book, fill queue and escrow accounts.
+#[derive(Accounts)]
+pub struct InitializeOrderBook<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
     #[account(
-        init_if_needed,
-        payer = authority,
-        mint::decimals = 0,
-        mint::authority = market,
-        seeds = [b"yes_mint", market.key().as_ref()],
+        has_one = authority @ ErrorCode::Unauthorized,
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
         bump
     )]
-    pub yes_mint: Account<'info, Mint>,
+    pub market: Box<Account<'info, Market>>,
 
     #[account(
-        init_if_needed,
+        init,
         payer = authority,
-        mint::decimals = 0,
-        mint::authority = market,
-        seeds = [b"no_mint", market.key().as_ref()],
+        space = 8 + OrderBook::INIT_SPACE,
+        seeds = [b"order_book", market.key().as_ref()],
         bump
     )]
-    pub no_mint: Account<'info, Mint>,
-
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-}
-
-const METADATA_PROGRAM_ID: Pubkey = pubkey!(
-    Pubkey::new_from_array([
-        11, 112, 101, 177, 227, 209, 124, 69, 56, 157, 82, 127, 107, 4, 195, 205, 88, 184, 108, 115,
-        26, 160, 253, 181, 73, 182, 209, 188, 3, 248, 41, 70,
-    ])
-);
+    pub order_book: Box<Account<'info, OrderBook>>,
 
-#[derive(Accounts)]
-pub struct CreateMint<'info> {
     #[account(
-        mut,
-        seeds = [b"market".as_ref(), market.authority.key().as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes()],
+        init,
+        payer = authority,
+        space = 8 + EventQueue::INIT_SPACE,
+        seeds = [b"event_queue", market.key().as_ref()],
         bump
     )]
-    pub market: Account<'info, Market>,
+    pub event_queue: Box<Account<'info, EventQueue>>,
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
-
-    // The PDA is both the address of the mint account and the mint authority
     #[account(
-        init,
         seeds = [b"yes_mint", market.key().as_ref()],
         bump,
-        payer = authority,
-        mint::decimals = 0,
-        mint::authority = yes_mint
+        mint::token_program = token_program,
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
     )]
-    pub yes_mint: Account<'info, Mint>,
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         init,
-        seeds = [b"no_mint", market.key().as_ref()],
+        payer = authority,
+        seeds = [b"book_collateral", market.key().as_ref()],
         bump,
+        token::mint = collateral_mint,
+        token::authority = market,
+        token::token_program = collateral_token_program,
+    )]
+    pub book_collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
         payer = authority,
-        mint::decimals = 0,
-        mint::authority = no_mint
-    )]
-    pub no_mint: Account<'info, Mint>,
-
-    ///CHECK: Using "address" constraint to validate metadata account address
-    #[account(mut,
-        address = Pubkey::find_program_address(
-            &[b"metadata".as_ref(), METAPLEX_PROGRAM_ID.as_ref(), yes_mint.key().as_ref()],
-            &METADATA_PROGRAM_ID,
-        ).0)]
-    pub yes_metadata_account: UncheckedAccount<'info>,
-
-    ///CHECK: Using "address" constraint to validate metadata account address
-    #[account(mut,
-            address = Pubkey::find_program_address(
-                &[b"metadata".as_ref(), METAPLEX_PROGRAM_ID.as_ref(), no_mint.key().as_ref()],
-                &METADATA_PROGRAM_ID,
-            ).0)]
-    pub no_metadata_account: UncheckedAccount<'info>,
-
-    pub token_program: Program<'info, Token>,
-    pub token_metadata_program: Program<'info, Metadata>,
+        seeds = [b"book_yes", market.key().as_ref()],
+        bump,
+        token::mint = yes_mint,
+        token::authority = market,
+        token::token_program = token_program,
+    )]
+    pub book_yes_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
 }
 
+/// A user's own order book action (`place_order`, `cancel_order`, `settle_funds`).
+/// `place_order` on a full side also takes the evicted owner's `OpenOrders`
+/// as a remaining account.
 #[derive(Accounts)]
-pub struct MintMetadataTokens<'info> {
+pub struct ManageOrders<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
     #[account(
         mut,
-        seeds = [b"market".as_ref(), market.authority.key().as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes()],
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
         bump
     )]
-    pub market: Account<'info, Market>,
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
 
     #[account(
         mut,
+        seeds = [b"order_book", market.key().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Box<Account<'info, OrderBook>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + OpenOrders::INIT_SPACE,
+        seeds = [b"open_orders", market.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(
         seeds = [b"yes_mint", market.key().as_ref()],
         bump,
-        mint::authority = yes_mint,
+        mint::token_program = token_program,
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"book_collateral", market.key().as_ref()],
+        bump,
     )]
-    pub yes_mint: Account<'info, Mint>,
+    pub book_collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
     #[account(
         mut,
-        seeds = [b"no_mint", market.key().as_ref()],
+        seeds = [b"book_yes", market.key().as_ref()],
         bump,
-        mint::authority = no_mint,
     )]
-    pub no_mint: Account<'info, Mint>,
+    pub book_yes_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
-        payer = payer,
+        payer = user,
         associated_token::mint = yes_mint,
-        associated_token::authority = market
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
     )]
-    pub treasury_yes_token_account: Account<'info, TokenAccount>,
+    pub user_yes_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
     #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = no_mint,
-        associated_token::authority = market
+        mut,
+        token::mint = collateral_mint,
+        token::authority = user,
+        token::token_program = collateral_token_program,
     )]
-    pub treasury_no_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    pub user_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
+/// Permissionless crank crossing the best bid and ask.
 #[derive(Accounts)]
-pub struct InitializeTreasuryTokenAccounts<'info> {
+pub struct MatchOrders<'info> {
+    #[account(mut)]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
     #[account(
         mut,
-        seeds = [b"market".as_ref(), market.authority.key().as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes()],
-        bump
+        seeds = [b"order_book", market.key().as_ref()],
+        bump = order_book.bump
     )]
-    pub market: Account<'info, Market>,
+    pub order_book: Box<Account<'info, OrderBook>>,
+
+    #[account(
+        mut,
+        seeds = [b"event_queue", market.key().as_ref()],
+        bump = event_queue.bump
+    )]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+}
+
+/// Permissionless crank crediting fills to `OpenOrders`, which are passed
+/// as remaining accounts.
+#[derive(Accounts)]
+pub struct ConsumeEvents<'info> {
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [b"event_queue", market.key().as_ref()],
+        bump = event_queue.bump
+    )]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+}
 
+/// Directional trades that mint or burn complete sets and route the
+/// unwanted leg through the market's pool (`buy_yes`, `buy_no`, `sell_yes`,
+/// `sell_no`).
+#[derive(Accounts)]
+pub struct PoolTrade<'info> {
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub user: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"yes_mint", market.key().as_ref()],
+        seeds = [b"market", market.authority.as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes(), market.direction.seed(), &market.terms_seed()],
         bump
     )]
-    pub yes_mint: Account<'info, Mint>,
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(seeds = [b"config".as_ref()], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", market.key().as_ref()],
+        bump = pool.bump,
+        has_one = market,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"yes_mint", market.key().as_ref()],
+        bump,
+        mint::token_program = token_program,
+        mint::authority = market,
+    )]
+    pub yes_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         seeds = [b"no_mint", market.key().as_ref()],
-        bump
+        bump,
+        mint::token_program = token_program,
+        mint::authority = market,
     )]
-    pub no_mint: Account<'info, Mint>,
+    pub no_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        init_if_needed,
-        payer = authority,
+        mut,
         associated_token::mint = yes_mint,
-        associated_token::authority = market
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
     )]
-    pub treasury_yes_token_account: Account<'info, TokenAccount>,
+    pub pool_yes_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        init_if_needed,
-        payer = authority,
+        mut,
         associated_token::mint = no_mint,
-        associated_token::authority = market
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
     )]
-    pub treasury_no_token_account: Account<'info, TokenAccount>,
+    pub pool_no_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-}
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = yes_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_yes_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-#[derive(Accounts)]
-pub struct MintOutcomeTokens<'info> {
     #[account(
-        mut,
-        seeds = [b"market".as_ref(), market.authority.key().as_ref(), &market.strike.to_le_bytes(), &market.expiry.to_le_bytes()],
-        bump
+        init_if_needed,
+        payer = user,
+        associated_token::mint = no_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
     )]
-    pub market: Account<'info, Market>,
+    pub user_no_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    #[account(
+        address = market.collateral_mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
-        seeds = [b"yes_mint", market.key().as_ref()],
-        bump
+        associated_token::mint = collateral_mint,
+        associated_token::authority = market,
+        associated_token::token_program = collateral_token_program,
     )]
-    pub yes_mint: Account<'info, Mint>,
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
-        seeds = [b"no_mint", market.key().as_ref()],
-        bump
+        token::mint = collateral_mint,
+        token::authority = user,
+        token::token_program = collateral_token_program,
     )]
-    pub no_mint: Account<'info, Mint>,
+    pub user_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
-        associated_token::mint = yes_mint,
-        associated_token::authority = market,
+        seeds = [b"treasury".as_ref(), collateral_mint.key().as_ref()],
+        bump = treasury.bump
     )]
-    pub treasury_yes_token_account: Account<'info, TokenAccount>,
+    pub treasury: Box<Account<'info, Treasury>>,
 
     #[account(
         mut,
-        associated_token::mint = no_mint,
-        associated_token::authority = market,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = collateral_token_program,
     )]
-    pub treasury_no_token_account: Account<'info, TokenAccount>,
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
 
-    pub token_program: Program<'info, Token>,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rescale_to_a_finer_exponent_multiplies() {
+        assert_eq!(FixedPrice::new(15_237, -2).rescale(-4).unwrap(), 1_523_700);
+        assert_eq!(FixedPrice::new(-15_237, -2).rescale(-4).unwrap(), -1_523_700);
+        assert_eq!(FixedPrice::new(3, 2).rescale(0).unwrap(), 300);
+    }
+
+    #[test]
+    fn rescale_to_a_coarser_exponent_must_be_exact() {
+        assert_eq!(FixedPrice::new(1_523_700, -4).rescale(-2).unwrap(), 15_237);
+        assert_eq!(FixedPrice::new(300, 0).rescale(2).unwrap(), 3);
+        assert_eq!(FixedPrice::new(15_237, -2).rescale(0).unwrap_err(), ErrorCode::PricePrecisionLoss.into());
+    }
+
+    #[test]
+    fn rescale_rejects_overflow_and_out_of_range_exponents() {
+        assert_eq!(FixedPrice::new(i128::MAX, 0).rescale(-1).unwrap_err(), ErrorCode::Overflow.into());
+        assert_eq!(FixedPrice::new(1_000, MAX_PRICE_EXPONENT).rescale(MIN_PRICE_EXPONENT).unwrap_err(), ErrorCode::Overflow.into());
+        assert_eq!(FixedPrice::new(1, MAX_PRICE_EXPONENT + 1).rescale(0).unwrap_err(), ErrorCode::InvalidPriceExponent.into());
+        assert_eq!(FixedPrice::new(1, 0).rescale(MIN_PRICE_EXPONENT - 1).unwrap_err(), ErrorCode::InvalidPriceExponent.into());
+    }
+
+    #[test]
+    fn checked_cmp_is_exact_across_exponents() {
+        let strike = FixedPrice::new(150, 0);
+        assert_eq!(FixedPrice::new(15_000, -2).checked_cmp(&strike).unwrap(), Ordering::Equal);
+        assert_eq!(strike.checked_cmp(&FixedPrice::new(15, 1)).unwrap(), Ordering::Equal);
+        assert_eq!(FixedPrice::new(15_001, -2).checked_cmp(&strike).unwrap(), Ordering::Greater);
+        assert_eq!(FixedPrice::new(14_999_999_999, -8).checked_cmp(&strike).unwrap(), Ordering::Less);
+    }
+
+    fn range(lower_inclusive: bool, upper_inclusive: bool) -> StrikeRange {
+        StrikeRange { upper_strike: 170, lower_inclusive, upper_inclusive }
+    }
+
+    #[test]
+    fn strike_range_contains_prices_strictly_inside() {
+        for (lower_inclusive, upper_inclusive) in [(false, false), (true, false), (false, true), (true, true)] {
+            let range = range(lower_inclusive, upper_inclusive);
+            assert!(range.contains(Ordering::Greater, Ordering::Less));
+            assert!(!range.contains(Ordering::Less, Ordering::Less));
+            assert!(!range.contains(Ordering::Greater, Ordering::Greater));
+        }
+    }
+
+    #[test]
+    fn strike_range_bounds_follow_inclusivity() {
+        assert!(range(true, false).contains(Ordering::Equal, Ordering::Less));
+        assert!(!range(false, true).contains(Ordering::Equal, Ordering::Less));
+        assert!(range(false, true).contains(Ordering::Greater, Ordering::Equal));
+        assert!(!range(true, false).contains(Ordering::Greater, Ordering::Equal));
+    }
+
+    #[test]
+    fn strike_range_seed_is_empty_only_for_single_strike_markets() {
+        assert!(StrikeRange::seed_for(&None).is_empty());
+        let closed = StrikeRange::seed_for(&Some(range(true, true)));
+        let open = StrikeRange::seed_for(&Some(range(false, false)));
+        assert!(closed.starts_with(b"range"));
+        assert_ne!(closed, open);
+    }
+
+    /// Bucket market on `breakpoints`, strikes in whole units.
+    fn bucket_market(breakpoints: &[u64]) -> Market {
+        let mut market = Market::try_deserialize_unchecked(&mut &[0u8; 8 + Market::INIT_SPACE][..]).unwrap();
+        market.strike = breakpoints[0];
+        market.bucket_count = (breakpoints.len() + 1) as u8;
+        market.breakpoints[..breakpoints.len()].copy_from_slice(breakpoints);
+        market
+    }
+
+    #[test]
+    fn bucket_for_counts_breakpoints_at_or_below_price() {
+        let market = bucket_market(&[150, 170]);
+        assert_eq!(market.bucket_for(&FixedPrice::new(149, 0)).unwrap(), 0);
+        assert_eq!(market.bucket_for(&FixedPrice::new(150, 0)).unwrap(), 1);
+        assert_eq!(market.bucket_for(&FixedPrice::new(16_999, -2)).unwrap(), 1);
+        assert_eq!(market.bucket_for(&FixedPrice::new(170, 0)).unwrap(), 2);
+        assert_eq!(market.bucket_for(&FixedPrice::new(1_000, 0)).unwrap(), 2);
+    }
+
+    #[test]
+    fn bucket_label_uses_interval_notation() {
+        let market = bucket_market(&[150, 170]);
+        assert_eq!(market.bucket_label(0), "<150");
+        assert_eq!(market.bucket_label(1), "[150-170)");
+        assert_eq!(market.bucket_label(2), ">=170");
+    }
+
+    #[test]
+    fn terms_seed_for_separates_market_kinds() {
+        let range = Some(range(true, false));
+        assert!(Market::terms_seed_for(&None, &[]).is_empty());
+        assert_eq!(Market::terms_seed_for(&range, &[]), StrikeRange::seed_for(&range));
+
+        let buckets = Market::terms_seed_for(&None, &[150, 170]);
+        assert_eq!(buckets.len(), 32);
+        assert_ne!(buckets, Market::terms_seed_for(&None, &[150, 180]));
+        assert_eq!(bucket_market(&[150, 170]).terms_seed(), buckets);
+    }
+
+    #[test]
+    fn strike_from_rounds_spot_to_nearest_step() {
+        let spot = FixedPrice::new(15_237, -2);
+        assert_eq!(StrikeRule::RoundToNearest { step: 1 }.strike_from(&spot, 0).unwrap(), 152);
+        assert_eq!(StrikeRule::RoundToNearest { step: 5 }.strike_from(&spot, 0).unwrap(), 150);
+        // Halves round up
+        assert_eq!(StrikeRule::RoundToNearest { step: 1 }.strike_from(&FixedPrice::new(1_525, -1), 0).unwrap(), 153);
+    }
+
+    #[test]
+    fn strike_from_applies_offset_before_rounding() {
+        let spot = FixedPrice::new(15_237, -2);
+        let above = StrikeRule::SpotOffset { offset_bps: 500, step: 1 };
+        let below = StrikeRule::SpotOffset { offset_bps: -500, step: 1 };
+        assert_eq!(above.strike_from(&spot, 0).unwrap(), 160);
+        assert_eq!(below.strike_from(&spot, 0).unwrap(), 145);
+    }
+
+    #[test]
+    fn strike_from_works_in_finer_strike_exponents() {
+        let spot = FixedPrice::new(15_237, -2);
+        // Step of 0.01 with strikes in 10^-4
+        assert_eq!(StrikeRule::RoundToNearest { step: 100 }.strike_from(&spot, -4).unwrap(), 1_523_700);
+    }
+
+    #[test]
+    fn strike_rule_rejects_degenerate_strikes() {
+        assert!(StrikeRule::RoundToNearest { step: 0 }.validate().is_err());
+        assert!(StrikeRule::SpotOffset { offset_bps: -10_000, step: 1 }.validate().is_err());
+        assert!(StrikeRule::RoundToNearest { step: 10 }.strike_from(&FixedPrice::new(2, 0), 0).is_err());
+    }
 }
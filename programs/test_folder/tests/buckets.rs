@@ -0,0 +1,170 @@
+mod common;
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::instruction::{AccountMeta, Instruction},
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{associated_token::spl_associated_token_account, token::spl_token, token_2022::spl_token_2022};
+use binary_options_sol::{
+    error::ErrorCode,
+    state::{ConfidenceMode, Market, MarketParams, MarketStatus, Outcome},
+};
+use common::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+const STRIKE: u64 = 50_000;
+const BUCKET_COUNT: u8 = 3;
+
+fn bucket_params() -> MarketParams {
+    MarketParams { breakpoints: vec![STRIKE, 60_000], ..market_params() }
+}
+
+fn bucket_mint_pda(market: &Pubkey, index: u8) -> Pubkey {
+    pda(&[b"bucket_mint", market.as_ref(), &[index]])
+}
+
+async fn open_bucket_market(env: &mut TestEnv, authority: &Keypair, expiry: i64) -> Pubkey {
+    let market = env.initialize_market(authority, STRIKE, expiry, bucket_params()).await;
+    let mut instructions: Vec<Instruction> = (0..BUCKET_COUNT)
+        .map(|index| Instruction {
+            program_id: binary_options_sol::ID,
+            accounts: binary_options_sol::accounts::CreateBucketMint {
+                market,
+                authority: authority.pubkey(),
+                asset_config: asset_config_pda(ASSET),
+                bucket_mint: bucket_mint_pda(&market, index),
+                token_program: spl_token_2022::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: binary_options_sol::instruction::CreateBucketMint { index }.data(),
+        })
+        .collect();
+    instructions.push(open_market_ix(&authority.pubkey(), &market));
+    env.send(&instructions, &[authority]).await.unwrap();
+    market
+}
+
+async fn mint_bucket_set(env: &mut TestEnv, user: &Keypair, market: Pubkey, amount: u64) {
+    let payer = env.payer().pubkey();
+    let mut instructions: Vec<Instruction> = (0..BUCKET_COUNT)
+        .map(|index| {
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer,
+                &user.pubkey(),
+                &bucket_mint_pda(&market, index),
+                &spl_token_2022::ID,
+            )
+        })
+        .collect();
+
+    let treasury = treasury_pda(&env.collateral_mint);
+    let mut accounts = binary_options_sol::accounts::MintBucketSet {
+        user: user.pubkey(),
+        market,
+        config: config_pda().0,
+        collateral_mint: env.collateral_mint,
+        collateral_vault: env.collateral_ata(&market),
+        user_collateral_account: env.collateral_ata(&user.pubkey()),
+        treasury,
+        treasury_vault: env.collateral_ata(&treasury),
+        token_program: spl_token_2022::ID,
+        collateral_token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+    for index in 0..BUCKET_COUNT {
+        let bucket_mint = bucket_mint_pda(&market, index);
+        accounts.push(AccountMeta::new(bucket_mint, false));
+        accounts.push(AccountMeta::new(outcome_ata(&user.pubkey(), &bucket_mint), false));
+    }
+    instructions.push(Instruction {
+        program_id: binary_options_sol::ID,
+        accounts,
+        data: binary_options_sol::instruction::MintBucketSet { amount }.data(),
+    });
+    env.send(&instructions, &[user]).await.unwrap();
+}
+
+fn redeem_bucket_ix(env: &TestEnv, user: &Pubkey, market: &Pubkey, index: u8) -> Instruction {
+    let treasury = treasury_pda(&env.collateral_mint);
+    let bucket_mint = bucket_mint_pda(market, index);
+    Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::RedeemBucket {
+            user: *user,
+            market: *market,
+            config: config_pda().0,
+            bucket_mint,
+            user_bucket_token_account: outcome_ata(user, &bucket_mint),
+            collateral_mint: env.collateral_mint,
+            collateral_vault: env.collateral_ata(market),
+            user_collateral_account: env.collateral_ata(user),
+            treasury,
+            treasury_vault: env.collateral_ata(&treasury),
+            token_program: spl_token_2022::ID,
+            collateral_token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::RedeemBucket { index }.data(),
+    }
+}
+
+#[tokio::test]
+async fn winning_bucket_redeems_and_market_closes() {
+    let mut env = TestEnv::start().await;
+    let authority = env.new_user().await;
+    let user = env.new_user().await;
+    let expiry = env.now().await + 3_600;
+    let market = open_bucket_market(&mut env, &authority, expiry).await;
+    mint_bucket_set(&mut env, &user, market, 10).await;
+    assert_eq!(env.token_balance(env.collateral_ata(&user.pubkey())).await, USER_COLLATERAL - 10 * 1_000);
+
+    env.warp_to(expiry + 1).await;
+    let price = env.set_price(55_000, 10, expiry + 1).await;
+    env.send(&[resolve_market_ix(&env.payer().pubkey(), &market, &price)], &[]).await.unwrap();
+    let resolved: Market = env.account(market).await;
+    assert_eq!(resolved.status, MarketStatus::Resolved);
+    assert_eq!((resolved.outcome, resolved.winning_bucket), (Some(Outcome::Yes), 1));
+
+    let redeems: Vec<Instruction> = (0..BUCKET_COUNT)
+        .map(|index| redeem_bucket_ix(&env, &user.pubkey(), &market, index))
+        .collect();
+    env.send(&redeems, &[&user]).await.unwrap();
+    assert_eq!(env.token_balance(env.collateral_ata(&user.pubkey())).await, USER_COLLATERAL);
+    for index in 0..BUCKET_COUNT {
+        assert_eq!(env.mint_supply(bucket_mint_pda(&market, index)).await, 0);
+        assert!(!env.exists(outcome_ata(&user.pubkey(), &bucket_mint_pda(&market, index))).await);
+    }
+
+    let mut accounts = binary_options_sol::accounts::CloseMarket {
+        authority: authority.pubkey(),
+        market,
+        yes_mint: None,
+        no_mint: None,
+        collateral_mint: env.collateral_mint,
+        collateral_vault: env.collateral_ata(&market),
+        authority_collateral_account: env.collateral_ata(&authority.pubkey()),
+        collateral_token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend((0..BUCKET_COUNT).map(|index| AccountMeta::new_readonly(bucket_mint_pda(&market, index), false)));
+    let close_market = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts,
+        data: binary_options_sol::instruction::CloseMarket {}.data(),
+    };
+    env.send(&[close_market], &[&authority]).await.unwrap();
+    assert_eq!(env.account::<Market>(market).await.status, MarketStatus::Closed);
+    assert!(!env.exists(env.collateral_ata(&market)).await);
+}
+
+#[tokio::test]
+async fn bucket_markets_require_reject_confidence_mode() {
+    let mut env = TestEnv::start().await;
+    let authority = env.new_user().await;
+    let expiry = env.now().await + 3_600;
+    let params = MarketParams { confidence_mode: ConfidenceMode::BandMustClear, ..bucket_params() };
+    let (_, instruction) = env.initialize_market_ix(&authority.pubkey(), STRIKE, expiry, params);
+    assert_error(env.send(&[instruction], &[&authority]).await, ErrorCode::UnsupportedMarketType);
+}
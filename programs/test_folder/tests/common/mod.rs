@@ -0,0 +1,439 @@
+#![allow(dead_code)]
+
+use anchor_lang::{
+    prelude::*,
+    system_program,
+    solana_program::{entrypoint::ProgramResult, instruction::{AccountMeta, Instruction}, program_pack::Pack, system_instruction},
+    AccountDeserialize, Discriminator, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, spl_associated_token_account},
+    token::spl_token,
+    token_2022::spl_token_2022::{self, extension::StateWithExtensions},
+};
+use binary_options_sol::state::{
+    AssetConfigParams, ConfidenceMode, Direction, Market, MarketParams, ProgramConfig,
+    CONFIG_RESERVED_BYTES,
+};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Keypair, Signer},
+    instruction::InstructionError,
+    transaction::{Transaction, TransactionError},
+};
+
+pub const ASSET: u8 = 0;
+pub const FEED_ID: [u8; 32] = [7; 32];
+pub const PRICE_EXPONENT: i32 = -8;
+pub const SETTLEMENT_WINDOW: u64 = 10;
+pub const USER_COLLATERAL: u64 = 1_000_000_000;
+
+fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    // Anchor ties the account infos to the 'info lifetime of the entrypoint
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    binary_options_sol::entry(program_id, accounts, data)
+}
+
+/// A program-test validator with the config, one listed asset, a classic SPL
+/// collateral mint and its treasury already set up.
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub collateral_mint: Pubkey,
+}
+
+impl TestEnv {
+    pub async fn start() -> Self {
+        let mut program_test = ProgramTest::new(
+            "binary_options_sol",
+            binary_options_sol::ID,
+            processor!(process_instruction),
+        );
+        program_test.prefer_bpf(false);
+        let mut context = program_test.start_with_context().await;
+        let admin = context.payer.pubkey();
+
+        let (config, config_bump) = config_pda();
+        let program_config = ProgramConfig {
+            admin,
+            pending_admin: None,
+            fee_recipient: admin,
+            pause_flags: 0,
+            resolver_grace_period: 0,
+            force_resolve_timeout: 86_400,
+            bump: config_bump,
+            guardian: None,
+            deposit_fee_bps: 0,
+            redeem_fee_bps: 0,
+            max_creator_fee_bps: 0,
+            reserved: [0; CONFIG_RESERVED_BYTES],
+        };
+        let mut data = Vec::with_capacity(8 + ProgramConfig::INIT_SPACE);
+        program_config.try_serialize(&mut data).unwrap();
+        data.resize(8 + ProgramConfig::INIT_SPACE, 0);
+        context.set_account(&config, &program_account(binary_options_sol::ID, data).into());
+
+        let mint = Keypair::new();
+        let mut env = TestEnv { context, collateral_mint: mint.pubkey() };
+        env.create_collateral_mint(&mint).await;
+
+        let asset_config = asset_config_pda(ASSET);
+        env.send(
+            &[
+                Instruction {
+                    program_id: binary_options_sol::ID,
+                    accounts: binary_options_sol::accounts::AddAssetConfig {
+                        asset_config,
+                        admin,
+                        config,
+                        system_program: system_program::ID,
+                    }
+                    .to_account_metas(None),
+                    data: binary_options_sol::instruction::AddAssetConfig {
+                        asset: ASSET,
+                        params: AssetConfigParams {
+                            feed_id: FEED_ID,
+                            symbol: "BTC".to_string(),
+                            price_exponent: PRICE_EXPONENT,
+                            max_staleness: 60,
+                            max_confidence_bps: 100,
+                            strike_confidence_k_bps: 10_000,
+                            settlement_window: SETTLEMENT_WINDOW,
+                        },
+                    }
+                    .data(),
+                },
+                Instruction {
+                    program_id: binary_options_sol::ID,
+                    accounts: binary_options_sol::accounts::InitializeTreasury {
+                        treasury: treasury_pda(&env.collateral_mint),
+                        collateral_mint: env.collateral_mint,
+                        treasury_vault: env.collateral_ata(&treasury_pda(&env.collateral_mint)),
+                        authority: admin,
+                        config,
+                        system_program: system_program::ID,
+                        collateral_token_program: spl_token::ID,
+                        associated_token_program: spl_associated_token_account::ID,
+                    }
+                    .to_account_metas(None),
+                    data: binary_options_sol::instruction::InitializeTreasury {}.data(),
+                },
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        env
+    }
+
+    pub fn payer(&self) -> Keypair {
+        self.context.payer.insecure_clone()
+    }
+
+    /// Signs with the payer plus `signers` and processes `instructions` in a
+    /// single transaction with the maximum compute budget.
+    pub async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> std::result::Result<(), BanksClientError> {
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(1_400_000)];
+        all.extend_from_slice(instructions);
+        let payer = self.payer();
+        let mut keypairs = vec![&payer];
+        keypairs.extend_from_slice(signers);
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(&all, Some(&payer.pubkey()), &keypairs, blockhash);
+        self.context.banks_client.process_transaction(transaction).await
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp
+    }
+
+    pub async fn warp_to(&mut self, unix_timestamp: i64) {
+        let mut clock = self.context.banks_client.get_sysvar::<Clock>().await.unwrap();
+        clock.unix_timestamp = unix_timestamp;
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, address: Pubkey) -> T {
+        let account = self.context.banks_client.get_account(address).await.unwrap().unwrap();
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn exists(&mut self, address: Pubkey) -> bool {
+        self.context.banks_client.get_account(address).await.unwrap().is_some()
+    }
+
+    pub async fn token_balance(&mut self, address: Pubkey) -> u64 {
+        let account = self.context.banks_client.get_account(address).await.unwrap().unwrap();
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    pub async fn mint_supply(&mut self, mint: Pubkey) -> u64 {
+        let account = self.context.banks_client.get_account(mint).await.unwrap().unwrap();
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data)
+            .unwrap()
+            .base
+            .supply
+    }
+
+    pub fn collateral_ata(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.collateral_mint, &spl_token::ID)
+    }
+
+    /// Remaining accounts `create_series` and `roll_market` list a market
+    /// with: the market, its collateral vault and its YES/NO mints.
+    pub fn listing_accounts(&self, market: &Pubkey) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(*market, false),
+            AccountMeta::new(self.collateral_ata(market), false),
+            AccountMeta::new(yes_mint_pda(market), false),
+            AccountMeta::new(no_mint_pda(market), false),
+        ]
+    }
+
+    async fn create_collateral_mint(&mut self, mint: &Keypair) {
+        let payer = self.payer().pubkey();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        self.send(
+            &[
+                system_instruction::create_account(
+                    &payer,
+                    &mint.pubkey(),
+                    rent.minimum_balance(spl_token::state::Mint::LEN),
+                    spl_token::state::Mint::LEN as u64,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), &payer, None, 6).unwrap(),
+            ],
+            &[mint],
+        )
+        .await
+        .unwrap();
+    }
+
+    /// Funds a new keypair with SOL and `USER_COLLATERAL` collateral in its ATA.
+    pub async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let payer = self.payer().pubkey();
+        let ata = self.collateral_ata(&user.pubkey());
+        self.send(
+            &[
+                system_instruction::transfer(&payer, &user.pubkey(), 10_000_000_000),
+                spl_associated_token_account::instruction::create_associated_token_account(
+                    &payer,
+                    &user.pubkey(),
+                    &self.collateral_mint,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::mint_to(&spl_token::ID, &self.collateral_mint, &ata, &payer, &[], USER_COLLATERAL)
+                    .unwrap(),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        user
+    }
+
+    /// Writes a fully verified Pyth price update for the test feed and
+    /// returns its address.
+    pub async fn set_price(&mut self, price: i64, conf: u64, publish_time: i64) -> Pubkey {
+        let address = Pubkey::new_unique();
+        let mut data = PriceUpdateV2::discriminator().to_vec();
+        data.extend_from_slice(Pubkey::new_unique().as_ref()); // write_authority
+        data.push(1); // VerificationLevel::Full
+        data.extend_from_slice(&FEED_ID);
+        data.extend_from_slice(&price.to_le_bytes());
+        data.extend_from_slice(&conf.to_le_bytes());
+        data.extend_from_slice(&PRICE_EXPONENT.to_le_bytes());
+        data.extend_from_slice(&publish_time.to_le_bytes());
+        data.extend_from_slice(&(publish_time - 1).to_le_bytes()); // prev_publish_time
+        data.extend_from_slice(&price.to_le_bytes()); // ema_price
+        data.extend_from_slice(&conf.to_le_bytes()); // ema_conf
+        data.extend_from_slice(&0u64.to_le_bytes()); // posted_slot
+        self.context.set_account(&address, &program_account(pyth_solana_receiver_sdk::ID, data).into());
+        address
+    }
+
+    /// Creates a YES/NO market through `initialize_market`, `create_mint` and
+    /// `open_market`, returning its address.
+    pub async fn open_market(&mut self, authority: &Keypair, strike: u64, expiry: i64, params: MarketParams) -> Pubkey {
+        let market = self.initialize_market(authority, strike, expiry, params).await;
+        self.send(
+            &[
+                Instruction {
+                    program_id: binary_options_sol::ID,
+                    accounts: binary_options_sol::accounts::CreateMint {
+                        market,
+                        authority: authority.pubkey(),
+                        asset_config: asset_config_pda(ASSET),
+                        yes_mint: yes_mint_pda(&market),
+                        no_mint: no_mint_pda(&market),
+                        token_program: spl_token_2022::ID,
+                        system_program: system_program::ID,
+                    }
+                    .to_account_metas(None),
+                    data: binary_options_sol::instruction::CreateMint {}.data(),
+                },
+                open_market_ix(&authority.pubkey(), &market),
+            ],
+            &[authority],
+        )
+        .await
+        .unwrap();
+        market
+    }
+
+    pub async fn initialize_market(&mut self, authority: &Keypair, strike: u64, expiry: i64, params: MarketParams) -> Pubkey {
+        let (market, instruction) = self.initialize_market_ix(&authority.pubkey(), strike, expiry, params);
+        self.send(&[instruction], &[authority]).await.unwrap();
+        market
+    }
+
+    pub fn initialize_market_ix(&self, authority: &Pubkey, strike: u64, expiry: i64, params: MarketParams) -> (Pubkey, Instruction) {
+        let terms = Market::terms_seed_for(&params.range, &params.breakpoints);
+        let market = market_pda(authority, strike, expiry, params.direction, &terms);
+        let instruction = Instruction {
+            program_id: binary_options_sol::ID,
+            accounts: binary_options_sol::accounts::InitializeMarket {
+                market,
+                authority: *authority,
+                asset_config: asset_config_pda(ASSET),
+                config: config_pda().0,
+                collateral_mint: self.collateral_mint,
+                authority_collateral_account: None,
+                collateral_vault: self.collateral_ata(&market),
+                system_program: system_program::ID,
+                collateral_token_program: spl_token::ID,
+                associated_token_program: spl_associated_token_account::ID,
+            }
+            .to_account_metas(None),
+            data: binary_options_sol::instruction::InitializeMarket { strike, expiry, asset: ASSET, params }.data(),
+        };
+        (market, instruction)
+    }
+
+    pub async fn mint_complete_set(&mut self, user: &Keypair, market: Pubkey, amount: u64) {
+        let treasury = treasury_pda(&self.collateral_mint);
+        let instruction = Instruction {
+            program_id: binary_options_sol::ID,
+            accounts: binary_options_sol::accounts::MintCompleteSet {
+                user: user.pubkey(),
+                market,
+                config: config_pda().0,
+                yes_mint: yes_mint_pda(&market),
+                no_mint: no_mint_pda(&market),
+                user_yes_token_account: outcome_ata(&user.pubkey(), &yes_mint_pda(&market)),
+                user_no_token_account: outcome_ata(&user.pubkey(), &no_mint_pda(&market)),
+                collateral_mint: self.collateral_mint,
+                collateral_vault: self.collateral_ata(&market),
+                user_collateral_account: self.collateral_ata(&user.pubkey()),
+                treasury,
+                treasury_vault: self.collateral_ata(&treasury),
+                system_program: system_program::ID,
+                token_program: spl_token_2022::ID,
+                collateral_token_program: spl_token::ID,
+                associated_token_program: spl_associated_token_account::ID,
+            }
+            .to_account_metas(None),
+            data: binary_options_sol::instruction::MintCompleteSet { amount }.data(),
+        };
+        self.send(&[instruction], &[user]).await.unwrap();
+    }
+}
+
+fn program_account(owner: Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Market terms for a plain YES/NO market paying 1_000 collateral per token.
+pub fn market_params() -> MarketParams {
+    MarketParams {
+        collateral_per_token: 1_000,
+        strike_exponent: PRICE_EXPONENT,
+        confidence_mode: ConfidenceMode::Reject,
+        resolver: None,
+        creator_fee_bps: 0,
+        direction: Direction::AboveOrEqual,
+        range: None,
+        breakpoints: vec![],
+        lmsr_b: 0,
+    }
+}
+
+pub fn resolve_market_ix(signer: &Pubkey, market: &Pubkey, price_account: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::ResolveMarket {
+            signer: *signer,
+            market: *market,
+            config: config_pda().0,
+            asset_config: asset_config_pda(ASSET),
+            price_account: *price_account,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::ResolveMarket {}.data(),
+    }
+}
+
+pub fn open_market_ix(authority: &Pubkey, market: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::MarketAuthority { authority: *authority, market: *market }
+            .to_account_metas(None),
+        data: binary_options_sol::instruction::OpenMarket {}.data(),
+    }
+}
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &binary_options_sol::ID).0
+}
+
+pub fn config_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], &binary_options_sol::ID)
+}
+
+pub fn asset_config_pda(asset: u8) -> Pubkey {
+    pda(&[b"asset_config", &[asset]])
+}
+
+pub fn treasury_pda(collateral_mint: &Pubkey) -> Pubkey {
+    pda(&[b"treasury", collateral_mint.as_ref()])
+}
+
+pub fn market_pda(authority: &Pubkey, strike: u64, expiry: i64, direction: Direction, terms: &[u8]) -> Pubkey {
+    pda(&[b"market", authority.as_ref(), &strike.to_le_bytes(), &expiry.to_le_bytes(), direction.seed(), terms])
+}
+
+pub fn yes_mint_pda(market: &Pubkey) -> Pubkey {
+    pda(&[b"yes_mint", market.as_ref()])
+}
+
+pub fn no_mint_pda(market: &Pubkey) -> Pubkey {
+    pda(&[b"no_mint", market.as_ref()])
+}
+
+/// Token-2022 ATA for an outcome mint.
+pub fn outcome_ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, &spl_token_2022::ID)
+}
+
+/// Asserts a transaction from `TestEnv::send` failed in its first program
+/// instruction with `error`.
+pub fn assert_error(result: std::result::Result<(), BanksClientError>, error: binary_options_sol::error::ErrorCode) {
+    let err = result.expect_err("transaction should fail").unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::Custom(error.into()))
+    );
+}
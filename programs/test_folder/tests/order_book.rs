@@ -0,0 +1,156 @@
+mod common;
+
+use anchor_lang::{
+    prelude::*,
+    system_program,
+    solana_program::instruction::{AccountMeta, Instruction},
+    InstructionData, ToAccountMetas,
+};
+use anchor_spl::{associated_token::spl_associated_token_account, token::spl_token, token_2022::spl_token_2022};
+use binary_options_sol::{
+    error::ErrorCode,
+    state::{OpenOrders, OrderBook, OrderSide},
+};
+use common::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+const MIN_ORDER_SIZE: u64 = 10;
+
+fn open_orders_pda(market: &Pubkey, user: &Pubkey) -> Pubkey {
+    pda(&[b"open_orders", market.as_ref(), user.as_ref()])
+}
+
+fn manage_orders_accounts(env: &TestEnv, market: &Pubkey, user: &Pubkey) -> Vec<AccountMeta> {
+    binary_options_sol::accounts::ManageOrders {
+        user: *user,
+        market: *market,
+        config: config_pda().0,
+        order_book: pda(&[b"order_book", market.as_ref()]),
+        open_orders: open_orders_pda(market, user),
+        yes_mint: yes_mint_pda(market),
+        collateral_mint: env.collateral_mint,
+        book_collateral_vault: pda(&[b"book_collateral", market.as_ref()]),
+        book_yes_vault: pda(&[b"book_yes", market.as_ref()]),
+        user_yes_token_account: outcome_ata(user, &yes_mint_pda(market)),
+        user_collateral_account: env.collateral_ata(user),
+        system_program: system_program::ID,
+        token_program: spl_token_2022::ID,
+        collateral_token_program: spl_token::ID,
+        associated_token_program: spl_associated_token_account::ID,
+    }
+    .to_account_metas(None)
+}
+
+fn place_order_ix(env: &TestEnv, market: &Pubkey, user: &Pubkey, side: OrderSide, price: u64, quantity: u64) -> Instruction {
+    Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: manage_orders_accounts(env, market, user),
+        data: binary_options_sol::instruction::PlaceOrder { side, price, quantity }.data(),
+    }
+}
+
+fn settle_funds_ix(env: &TestEnv, market: &Pubkey, user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: manage_orders_accounts(env, market, user),
+        data: binary_options_sol::instruction::SettleFunds {}.data(),
+    }
+}
+
+async fn market_with_book(env: &mut TestEnv, authority: &Keypair) -> Pubkey {
+    let expiry = env.now().await + 3_600;
+    let market = env.open_market(authority, 50_000, expiry, market_params()).await;
+    let instruction = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::InitializeOrderBook {
+            authority: authority.pubkey(),
+            market,
+            order_book: pda(&[b"order_book", market.as_ref()]),
+            event_queue: pda(&[b"event_queue", market.as_ref()]),
+            yes_mint: yes_mint_pda(&market),
+            collateral_mint: env.collateral_mint,
+            book_collateral_vault: pda(&[b"book_collateral", market.as_ref()]),
+            book_yes_vault: pda(&[b"book_yes", market.as_ref()]),
+            system_program: system_program::ID,
+            token_program: spl_token_2022::ID,
+            collateral_token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::InitializeOrderBook { min_order_size: MIN_ORDER_SIZE }.data(),
+    };
+    env.send(&[instruction], &[authority]).await.unwrap();
+    market
+}
+
+#[tokio::test]
+async fn crossing_orders_match_and_settle() {
+    let mut env = TestEnv::start().await;
+    let authority = env.new_user().await;
+    let market = market_with_book(&mut env, &authority).await;
+    let seller = env.new_user().await;
+    let buyer = env.new_user().await;
+    env.mint_complete_set(&seller, market, 100).await;
+
+    let ask = place_order_ix(&env, &market, &seller.pubkey(), OrderSide::Ask, 600, 100);
+    env.send(&[ask], &[&seller]).await.unwrap();
+    let bid = place_order_ix(&env, &market, &buyer.pubkey(), OrderSide::Bid, 650, 60);
+    env.send(&[bid], &[&buyer]).await.unwrap();
+
+    let event_queue = pda(&[b"event_queue", market.as_ref()]);
+    let match_orders = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::MatchOrders {
+            market,
+            config: config_pda().0,
+            order_book: pda(&[b"order_book", market.as_ref()]),
+            event_queue,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::MatchOrders { limit: 8 }.data(),
+    };
+    env.send(&[match_orders], &[]).await.unwrap();
+
+    let book: OrderBook = env.account(pda(&[b"order_book", market.as_ref()])).await;
+    assert!(book.bids.is_empty());
+    assert_eq!(book.asks.len(), 1);
+    assert_eq!(book.asks[0].quantity, 40);
+
+    let mut accounts = binary_options_sol::accounts::ConsumeEvents { market, event_queue }.to_account_metas(None);
+    accounts.push(AccountMeta::new(open_orders_pda(&market, &buyer.pubkey()), false));
+    accounts.push(AccountMeta::new(open_orders_pda(&market, &seller.pubkey()), false));
+    let consume_events = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts,
+        data: binary_options_sol::instruction::ConsumeEvents { limit: 8 }.data(),
+    };
+    env.send(&[consume_events], &[]).await.unwrap();
+
+    // The resting ask sets the price; the bid gets its 50 per YES improvement back
+    let buyer_orders: OpenOrders = env.account(open_orders_pda(&market, &buyer.pubkey())).await;
+    assert_eq!((buyer_orders.collateral_free, buyer_orders.yes_free), (60 * 50, 60));
+    let seller_orders: OpenOrders = env.account(open_orders_pda(&market, &seller.pubkey())).await;
+    assert_eq!((seller_orders.collateral_free, seller_orders.yes_free), (60 * 600, 0));
+
+    env.send(&[settle_funds_ix(&env, &market, &buyer.pubkey())], &[&buyer]).await.unwrap();
+    env.send(&[settle_funds_ix(&env, &market, &seller.pubkey())], &[&seller]).await.unwrap();
+
+    let yes_mint = yes_mint_pda(&market);
+    assert_eq!(env.token_balance(outcome_ata(&buyer.pubkey(), &yes_mint)).await, 60);
+    assert_eq!(env.token_balance(env.collateral_ata(&buyer.pubkey())).await, USER_COLLATERAL - 60 * 600);
+    assert_eq!(
+        env.token_balance(env.collateral_ata(&seller.pubkey())).await,
+        USER_COLLATERAL - 100 * 1_000 + 60 * 600
+    );
+    assert_eq!(env.token_balance(pda(&[b"book_yes", market.as_ref()])).await, 40);
+}
+
+#[tokio::test]
+async fn orders_below_the_minimum_are_rejected() {
+    let mut env = TestEnv::start().await;
+    let authority = env.new_user().await;
+    let market = market_with_book(&mut env, &authority).await;
+    let buyer = env.new_user().await;
+
+    let bid = place_order_ix(&env, &market, &buyer.pubkey(), OrderSide::Bid, 500, MIN_ORDER_SIZE - 1);
+    assert_error(env.send(&[bid], &[&buyer]).await, ErrorCode::OrderTooSmall);
+}
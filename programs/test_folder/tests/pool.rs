@@ -0,0 +1,190 @@
+mod common;
+
+use anchor_lang::{prelude::*, solana_program::instruction::Instruction, system_program, InstructionData, ToAccountMetas};
+use anchor_spl::{associated_token::spl_associated_token_account, token::spl_token, token_2022::spl_token_2022};
+use binary_options_sol::{
+    error::ErrorCode,
+    state::{Market, MarketStatus, Pool, MINIMUM_LIQUIDITY},
+};
+use common::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+const STRIKE: u64 = 50_000;
+const DEPOSIT: u64 = 1_000;
+
+fn pool_pda(market: &Pubkey) -> Pubkey {
+    pda(&[b"pool", market.as_ref()])
+}
+
+fn lp_mint_pda(market: &Pubkey) -> Pubkey {
+    pda(&[b"lp_mint", pool_pda(market).as_ref()])
+}
+
+/// Opens a market with a fee-free pool seeded by `lp` with `DEPOSIT` YES and NO.
+async fn market_with_pool(env: &mut TestEnv, authority: &Keypair, lp: &Keypair, expiry: i64) -> Pubkey {
+    let market = env.open_market(authority, STRIKE, expiry, market_params()).await;
+    let pool = pool_pda(&market);
+    let initialize_pool = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::InitializePool {
+            authority: authority.pubkey(),
+            market,
+            pool,
+            lp_mint: lp_mint_pda(&market),
+            yes_mint: yes_mint_pda(&market),
+            no_mint: no_mint_pda(&market),
+            pool_yes_vault: outcome_ata(&pool, &yes_mint_pda(&market)),
+            pool_no_vault: outcome_ata(&pool, &no_mint_pda(&market)),
+            system_program: system_program::ID,
+            token_program: spl_token_2022::ID,
+            associated_token_program: spl_associated_token_account::ID,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::InitializePool { fee_bps: 0 }.data(),
+    };
+    env.send(&[initialize_pool], &[authority]).await.unwrap();
+
+    env.mint_complete_set(lp, market, DEPOSIT).await;
+    let add_liquidity = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: pool_liquidity_accounts(&market, &lp.pubkey()),
+        data: binary_options_sol::instruction::AddLiquidity { max_yes: DEPOSIT, max_no: DEPOSIT, min_lp: 0 }.data(),
+    };
+    env.send(&[add_liquidity], &[lp]).await.unwrap();
+    market
+}
+
+fn pool_liquidity_accounts(market: &Pubkey, user: &Pubkey) -> Vec<AccountMeta> {
+    let pool = pool_pda(market);
+    binary_options_sol::accounts::PoolLiquidity {
+        user: *user,
+        market: *market,
+        config: config_pda().0,
+        pool,
+        lp_mint: lp_mint_pda(market),
+        yes_mint: yes_mint_pda(market),
+        no_mint: no_mint_pda(market),
+        pool_yes_vault: outcome_ata(&pool, &yes_mint_pda(market)),
+        pool_no_vault: outcome_ata(&pool, &no_mint_pda(market)),
+        user_yes_token_account: outcome_ata(user, &yes_mint_pda(market)),
+        user_no_token_account: outcome_ata(user, &no_mint_pda(market)),
+        user_lp_token_account: outcome_ata(user, &lp_mint_pda(market)),
+        pool_lp_vault: outcome_ata(&pool, &lp_mint_pda(market)),
+        system_program: system_program::ID,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+    }
+    .to_account_metas(None)
+}
+
+fn redeem_pool_reserves_ix(market: &Pubkey) -> Instruction {
+    let pool = pool_pda(market);
+    Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::PoolReserves {
+            market: *market,
+            pool,
+            lp_mint: lp_mint_pda(market),
+            yes_mint: yes_mint_pda(market),
+            no_mint: no_mint_pda(market),
+            pool_yes_vault: outcome_ata(&pool, &yes_mint_pda(market)),
+            pool_no_vault: outcome_ata(&pool, &no_mint_pda(market)),
+            pool_lp_vault: outcome_ata(&pool, &lp_mint_pda(market)),
+            token_program: spl_token_2022::ID,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::RedeemPoolReserves {}.data(),
+    }
+}
+
+fn redeem_ix(env: &TestEnv, user: &Pubkey, market: &Pubkey) -> Instruction {
+    let treasury = treasury_pda(&env.collateral_mint);
+    Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::Redeem {
+            user: *user,
+            market: *market,
+            config: config_pda().0,
+            yes_mint: yes_mint_pda(market),
+            no_mint: no_mint_pda(market),
+            user_yes_token_account: Some(outcome_ata(user, &yes_mint_pda(market))),
+            user_no_token_account: Some(outcome_ata(user, &no_mint_pda(market))),
+            collateral_mint: env.collateral_mint,
+            collateral_vault: env.collateral_ata(market),
+            user_collateral_account: env.collateral_ata(user),
+            treasury,
+            treasury_vault: env.collateral_ata(&treasury),
+            system_program: system_program::ID,
+            token_program: spl_token_2022::ID,
+            collateral_token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::Redeem {}.data(),
+    }
+}
+
+fn close_market_ix(env: &TestEnv, authority: &Pubkey, market: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::CloseMarket {
+            authority: *authority,
+            market: *market,
+            yes_mint: Some(yes_mint_pda(market)),
+            no_mint: Some(no_mint_pda(market)),
+            collateral_mint: env.collateral_mint,
+            collateral_vault: env.collateral_ata(market),
+            authority_collateral_account: env.collateral_ata(authority),
+            collateral_token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::CloseMarket {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn locked_pool_reserves_are_burned_so_the_market_closes() {
+    let mut env = TestEnv::start().await;
+    let authority = env.new_user().await;
+    let lp = env.new_user().await;
+    let expiry = env.now().await + 3_600;
+    let market = market_with_pool(&mut env, &authority, &lp, expiry).await;
+    assert_error(env.send(&[redeem_pool_reserves_ix(&market)], &[]).await, ErrorCode::MarketNotResolved);
+
+    env.warp_to(expiry + 1).await;
+    let price = env.set_price(55_000, 10, expiry + 1).await;
+    env.send(&[resolve_market_ix(&env.payer().pubkey(), &market, &price)], &[]).await.unwrap();
+
+    // The LP's own shares still have a claim on the reserves
+    assert_error(env.send(&[redeem_pool_reserves_ix(&market)], &[]).await, ErrorCode::LiquidityOutstanding);
+
+    let lp_shares = DEPOSIT - MINIMUM_LIQUIDITY;
+    let remove_liquidity = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: pool_liquidity_accounts(&market, &lp.pubkey()),
+        data: binary_options_sol::instruction::RemoveLiquidity { lp_amount: lp_shares, min_yes: 0, min_no: 0 }.data(),
+    };
+    env.send(&[remove_liquidity, redeem_ix(&env, &lp.pubkey(), &market)], &[&lp]).await.unwrap();
+    assert_eq!(
+        env.token_balance(env.collateral_ata(&lp.pubkey())).await,
+        USER_COLLATERAL - MINIMUM_LIQUIDITY * 1_000
+    );
+
+    // Only the locked reserves are left, and they hold the market open
+    let close_market = close_market_ix(&env, &authority.pubkey(), &market);
+    assert_error(env.send(&[close_market], &[&authority]).await, ErrorCode::OutstandingTokens);
+
+    env.send(&[redeem_pool_reserves_ix(&market)], &[]).await.unwrap();
+    let pool: Pool = env.account(pool_pda(&market)).await;
+    assert_eq!((pool.yes_reserve, pool.no_reserve), (0, 0));
+    assert_eq!(env.mint_supply(yes_mint_pda(&market)).await, 0);
+    assert_eq!(env.mint_supply(no_mint_pda(&market)).await, 0);
+
+    // The collateral backing the burned reserves goes to the creator
+    let close_market = close_market_ix(&env, &authority.pubkey(), &market);
+    env.send(&[close_market], &[&authority]).await.unwrap();
+    assert_eq!(env.account::<Market>(market).await.status, MarketStatus::Closed);
+    assert_eq!(
+        env.token_balance(env.collateral_ata(&authority.pubkey())).await,
+        USER_COLLATERAL + MINIMUM_LIQUIDITY * 1_000
+    );
+}
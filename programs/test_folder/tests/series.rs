@@ -0,0 +1,134 @@
+mod common;
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::{AccountMeta, Instruction}, system_instruction},
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{associated_token::spl_associated_token_account, token::spl_token, token_2022::spl_token_2022};
+use binary_options_sol::{
+    error::ErrorCode,
+    state::{ConfidenceMode, Direction, Market, MarketParams, MarketSeries, MarketStatus, Outcome},
+};
+use common::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+const STRIKES: [u64; 2] = [50_000, 60_000];
+
+fn series_pda(authority: &Pubkey, expiry: i64) -> Pubkey {
+    pda(&[b"series", authority.as_ref(), &[ASSET], &expiry.to_le_bytes()])
+}
+
+fn series_market(authority: &Pubkey, strike: u64, expiry: i64) -> Pubkey {
+    market_pda(authority, strike, expiry, Direction::AboveOrEqual, &[])
+}
+
+/// Initializes a series and lists `STRIKES` in it.
+async fn create_series(env: &mut TestEnv, authority: &Keypair, expiry: i64) -> Pubkey {
+    let series = series_pda(&authority.pubkey(), expiry);
+    let initialize_series = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::InitializeSeries {
+            series,
+            authority: authority.pubkey(),
+            asset_config: asset_config_pda(ASSET),
+            config: config_pda().0,
+            collateral_mint: env.collateral_mint,
+            system_program: system_program::ID,
+            collateral_token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::InitializeSeries {
+            expiry,
+            asset: ASSET,
+            params: MarketParams { confidence_mode: ConfidenceMode::BandMustClear, ..market_params() },
+        }
+        .data(),
+    };
+
+    let mut accounts = binary_options_sol::accounts::CreateSeries {
+        series,
+        authority: authority.pubkey(),
+        asset_config: asset_config_pda(ASSET),
+        collateral_mint: env.collateral_mint,
+        system_program: system_program::ID,
+        token_program: spl_token_2022::ID,
+        collateral_token_program: spl_token::ID,
+        associated_token_program: spl_associated_token_account::ID,
+    }
+    .to_account_metas(None);
+    for strike in STRIKES {
+        accounts.extend(env.listing_accounts(&series_market(&authority.pubkey(), strike, expiry)));
+    }
+    let create_series = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts,
+        data: binary_options_sol::instruction::CreateSeries { strikes: STRIKES.to_vec() }.data(),
+    };
+    env.send(&[initialize_series, create_series], &[authority]).await.unwrap();
+    series
+}
+
+#[tokio::test]
+async fn series_markets_settle_on_one_observation() {
+    let mut env = TestEnv::start().await;
+    let authority = env.new_user().await;
+    let expiry = env.now().await + 3_600;
+
+    // Lamports sent to a market address ahead of time must not block the listing
+    let payer = env.payer().pubkey();
+    let prefunded = series_market(&authority.pubkey(), STRIKES[1], expiry);
+    env.send(&[system_instruction::transfer(&payer, &prefunded, 1_000_000)], &[]).await.unwrap();
+
+    let series = create_series(&mut env, &authority, expiry).await;
+    let listed: MarketSeries = env.account(series).await;
+    assert_eq!(listed.strikes, STRIKES.to_vec());
+    for strike in STRIKES {
+        let market: Market = env.account(series_market(&authority.pubkey(), strike, expiry)).await;
+        assert_eq!((market.status, market.series), (MarketStatus::Open, Some(series)));
+    }
+
+    let user = env.new_user().await;
+    let low_market = series_market(&authority.pubkey(), STRIKES[0], expiry);
+    env.mint_complete_set(&user, low_market, 5).await;
+
+    env.warp_to(expiry + 1).await;
+    let price = env.set_price(55_000, 10, expiry + 1).await;
+    let mut accounts = binary_options_sol::accounts::ResolveSeries {
+        signer: payer,
+        series,
+        config: config_pda().0,
+        asset_config: asset_config_pda(ASSET),
+        price_account: Some(price),
+    }
+    .to_account_metas(None);
+    accounts.extend(STRIKES.map(|strike| AccountMeta::new(series_market(&authority.pubkey(), strike, expiry), false)));
+    let resolve_series = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts,
+        data: binary_options_sol::instruction::ResolveSeries {}.data(),
+    };
+    env.send(&[resolve_series], &[]).await.unwrap();
+
+    let settled: MarketSeries = env.account(series).await;
+    assert_eq!(settled.settlement.unwrap().price.mantissa, 55_000);
+    let outcomes = [Outcome::Yes, Outcome::No];
+    for (strike, outcome) in STRIKES.into_iter().zip(outcomes) {
+        let market: Market = env.account(series_market(&authority.pubkey(), strike, expiry)).await;
+        assert_eq!((market.status, market.outcome), (MarketStatus::Resolved, Some(outcome)));
+    }
+}
+
+#[tokio::test]
+async fn series_markets_cannot_resolve_individually() {
+    let mut env = TestEnv::start().await;
+    let authority = env.new_user().await;
+    let expiry = env.now().await + 3_600;
+    create_series(&mut env, &authority, expiry).await;
+
+    env.warp_to(expiry + 1).await;
+    let price = env.set_price(55_000, 10, expiry + 1).await;
+    let market = series_market(&authority.pubkey(), STRIKES[0], expiry);
+    let resolve_market = resolve_market_ix(&env.payer().pubkey(), &market, &price);
+    assert_error(env.send(&[resolve_market], &[]).await, ErrorCode::UnsupportedMarketType);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::instruction::Instruction,
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{associated_token::spl_associated_token_account, token::spl_token, token_2022::spl_token_2022};
+use binary_options_sol::{
+    error::ErrorCode,
+    state::{Direction, Market, MarketStatus, MarketTemplate, Outcome, StrikeRule},
+};
+use common::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+const INTERVAL: i64 = 3_600;
+
+fn template_pda(authority: &Pubkey) -> Pubkey {
+    pda(&[b"template", authority.as_ref(), &[ASSET], &INTERVAL.to_le_bytes()])
+}
+
+/// Opens a market expiring at `expiry` and starts a template from it.
+async fn start_template(env: &mut TestEnv, authority: &Keypair, expiry: i64) -> (Pubkey, Pubkey) {
+    let first_market = env.open_market(authority, 50_000, expiry, market_params()).await;
+    let template = template_pda(&authority.pubkey());
+    let initialize_template = Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::InitializeTemplate {
+            template,
+            authority: authority.pubkey(),
+            first_market,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::InitializeTemplate {
+            interval: INTERVAL,
+            strike_rule: StrikeRule::RoundToNearest { step: 1_000 },
+        }
+        .data(),
+    };
+    env.send(&[initialize_template], &[authority]).await.unwrap();
+    (template, first_market)
+}
+
+fn roll_market_ix(env: &TestEnv, payer: &Pubkey, template: &Pubkey, previous_market: &Pubkey, next_market: &Pubkey) -> Instruction {
+    let mut accounts = binary_options_sol::accounts::RollMarket {
+        payer: *payer,
+        template: *template,
+        previous_market: *previous_market,
+        config: config_pda().0,
+        asset_config: asset_config_pda(ASSET),
+        collateral_mint: env.collateral_mint,
+        system_program: system_program::ID,
+        token_program: spl_token_2022::ID,
+        collateral_token_program: spl_token::ID,
+        associated_token_program: spl_associated_token_account::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(env.listing_accounts(next_market));
+    Instruction {
+        program_id: binary_options_sol::ID,
+        accounts,
+        data: binary_options_sol::instruction::RollMarket {}.data(),
+    }
+}
+
+fn reset_template_ix(authority: &Pubkey, template: &Pubkey, market: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: binary_options_sol::ID,
+        accounts: binary_options_sol::accounts::ResetTemplate {
+            authority: *authority,
+            template: *template,
+            market: *market,
+        }
+        .to_account_metas(None),
+        data: binary_options_sol::instruction::ResetTemplate {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn roll_lists_next_market_at_rounded_settlement() {
+    let mut env = TestEnv::start().await;
+    let authority = env.new_user().await;
+    let expiry = env.now().await + INTERVAL;
+    let (template, first_market) = start_template(&mut env, &authority, expiry).await;
+
+    env.warp_to(expiry + 1).await;
+    let price = env.set_price(55_423, 10, expiry + 1).await;
+    env.send(&[resolve_market_ix(&env.payer().pubkey(), &first_market, &price)], &[]).await.unwrap();
+    assert_eq!(env.account::<Market>(first_market).await.outcome, Some(Outcome::Yes));
+
+    // Anyone can crank the roll
+    let crank = env.new_user().await;
+    let next_expiry = expiry + INTERVAL;
+    let next_market = market_pda(&authority.pubkey(), 55_000, next_expiry, Direction::AboveOrEqual, &[]);
+    let roll_market = roll_market_ix(&env, &crank.pubkey(), &template, &first_market, &next_market);
+    env.send(&[roll_market], &[&crank]).await.unwrap();
+
+    let rolled: MarketTemplate = env.account(template).await;
+    assert_eq!((rolled.current_market, rolled.rolls), (next_market, 1));
+    let market: Market = env.account(next_market).await;
+    assert_eq!((market.strike, market.expiry, market.status), (55_000, next_expiry, MarketStatus::Open));
+    assert_eq!(market.authority, authority.pubkey());
+}
+
+#[tokio::test]
+async fn roll_waits_for_settlement_and_creator_can_reset() {
+    let mut env = TestEnv::start().await;
+    let authority = env.new_user().await;
+    let expiry = env.now().await + INTERVAL;
+    let (template, first_market) = start_template(&mut env, &authority, expiry).await;
+
+    let payer = env.payer().pubkey();
+    let next_market = market_pda(&authority.pubkey(), 50_000, expiry + INTERVAL, Direction::AboveOrEqual, &[]);
+    let roll_market = roll_market_ix(&env, &payer, &template, &first_market, &next_market);
+    assert_error(env.send(&[roll_market], &[]).await, ErrorCode::MarketNotResolved);
+
+    let replacement = env.open_market(&authority, 52_000, expiry + INTERVAL, market_params()).await;
+    let stranger = env.new_user().await;
+    let reset = reset_template_ix(&stranger.pubkey(), &template, &replacement);
+    assert_error(env.send(&[reset], &[&stranger]).await, ErrorCode::Unauthorized);
+
+    let reset = reset_template_ix(&authority.pubkey(), &template, &replacement);
+    env.send(&[reset], &[&authority]).await.unwrap();
+    assert_eq!(env.account::<MarketTemplate>(template).await.current_market, replacement);
+}